@@ -1,9 +1,25 @@
 use anyhow::Result;
 use tracing::info;
+use crate::session::{SessionManager, SessionStatus};
 
 pub async fn execute(session: String) -> Result<()> {
     info!("Attaching to session: {}", session);
 
+    let manager = SessionManager::open()?;
+    let session_info = manager
+        .get_session(&session)
+        .ok_or_else(|| anyhow::anyhow!("Session '{}' not found", session))?;
+
+    match session_info.status {
+        SessionStatus::Preparing => anyhow::bail!("Session '{}' is still running setup", session),
+        SessionStatus::Failed => anyhow::bail!(
+            "Session '{}' failed setup, see {}",
+            session,
+            manager.log_path(&session_info.id).display()
+        ),
+        _ => {}
+    }
+
     println!("🔗 Attaching to session '{}'...", session);
     println!("\n💡 This is a stub implementation. Full functionality coming soon!");
 
@@ -0,0 +1,134 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tracing::info;
+use crate::config::Config;
+use crate::git::checkpoint::{self, Schedule, Trigger};
+use crate::session::{Session, SessionManager};
+
+/// How often the watcher looks at the worktree
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+fn find_session(manager: &SessionManager, session: &str) -> Result<Session> {
+    manager
+        .get_session(session)
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Session '{}' not found", session))
+}
+
+fn take(worktree: &Path, id: &str, trigger: Trigger, message: Option<&str>) -> Result<()> {
+    let previous = checkpoint::list(worktree, id)?.pop();
+    match checkpoint::create(worktree, id, trigger, message)? {
+        Some(cp) => println!(
+            "📸 Checkpoint {} ({}) — {}",
+            cp.number,
+            trigger,
+            checkpoint::diffstat(worktree, &cp, previous.as_ref())?
+        ),
+        None if trigger == Trigger::Manual => {
+            println!("No changes since the last checkpoint")
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+pub async fn create(session: String, message: Option<String>) -> Result<()> {
+    info!("Checkpointing session: {}", session);
+
+    let manager = SessionManager::open()?;
+    let session_info = find_session(&manager, &session)?;
+    let worktree = manager.git_worktree(&session_info)?;
+
+    take(worktree, &session_info.id, Trigger::Manual, message.as_deref())
+}
+
+pub async fn watch(session: String) -> Result<()> {
+    info!("Watching session for checkpoints: {}", session);
+
+    let config = Config::load()?;
+    let manager = SessionManager::open()?;
+    let session_info = find_session(&manager, &session)?;
+    let worktree = manager.git_worktree(&session_info)?;
+    let mut schedule = Schedule::new(&config.checkpoint, SystemTime::now()).context(
+        "Automatic checkpoints are disabled (set checkpoint.on_idle or checkpoint.interval_secs)",
+    )?;
+
+    println!("👀 Watching session '{}' for checkpoints (Ctrl-C to stop)", session);
+
+    // Without an agent to watch, the worktree changing is the activity
+    let mut last_tree = checkpoint::snapshot_tree(worktree)?;
+    let mut last_change = None;
+
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+
+        if !worktree.exists() {
+            println!("Worktree is gone, stopping");
+            return Ok(());
+        }
+
+        let tree = checkpoint::snapshot_tree(worktree)?;
+        if tree != last_tree {
+            last_tree = tree;
+            last_change = Some(SystemTime::now());
+        }
+
+        if let Some(trigger) = schedule.due(last_change, SystemTime::now()) {
+            take(worktree, &session_info.id, trigger, None)?;
+        }
+    }
+}
+
+pub async fn list(session: String) -> Result<()> {
+    info!("Listing checkpoints for session: {}", session);
+
+    let manager = SessionManager::open()?;
+    let session_info = find_session(&manager, &session)?;
+    let worktree = manager.git_worktree(&session_info)?;
+    let checkpoints = checkpoint::list(worktree, &session_info.id)?;
+
+    if checkpoints.is_empty() {
+        println!("No checkpoints for session '{}'", session);
+        println!("\n💡 Create one with: acta checkpoint {}", session);
+        return Ok(());
+    }
+
+    println!("Checkpoints for '{}':", session);
+    println!("=================\n");
+    println!("{:<4} {:<27} {:<16} {:<9} Changes", "#", "Created", "Age", "Trigger");
+    println!("{}", "-".repeat(80));
+
+    for (i, cp) in checkpoints.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|p| &checkpoints[p]);
+        println!(
+            "{:<4} {:<27} {:<16} {:<9} {}",
+            cp.number,
+            cp.created_at,
+            cp.age,
+            cp.trigger.to_string(),
+            checkpoint::diffstat(worktree, cp, previous)?
+        );
+    }
+
+    println!("\n💡 Use 'acta rollback {} <#>' to restore a checkpoint", session);
+
+    Ok(())
+}
+
+pub async fn rollback(session: String, number: u32) -> Result<()> {
+    info!("Rolling back session {} to checkpoint {}", session, number);
+
+    let manager = SessionManager::open()?;
+    let session_info = find_session(&manager, &session)?;
+    let worktree = manager.git_worktree(&session_info)?;
+
+    let safety = checkpoint::rollback(worktree, &session_info.id, number)?;
+
+    println!("⏪ Restored session '{}' to checkpoint {}", session, number);
+    if let Some(cp) = safety {
+        println!("   Previous state saved as checkpoint {}", cp.number);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,234 @@
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeSet;
+use std::fs::{self, OpenOptions};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Instant;
+use tracing::info;
+use crate::git::{checkpoint, diff};
+use crate::git::diff::FileStat;
+use crate::session::{Session, SessionManager};
+
+/// Lines of output shown for a failed test command
+const TAIL_LINES: usize = 20;
+
+struct Entry {
+    session: Session,
+    worktree: PathBuf,
+    tree: String,
+    stats: Vec<FileStat>,
+}
+
+/// Outcome of the `--test` command in one worktree
+struct TestRun {
+    /// `pass 3s`, `FAIL 3s` or `error`
+    result: String,
+    passed: bool,
+    /// Last lines of the output, for failures
+    tail: Vec<String>,
+    /// Session log the full output was appended to
+    log: PathBuf,
+}
+
+impl Entry {
+    fn added(&self) -> usize {
+        self.stats.iter().map(|s| s.added).sum()
+    }
+
+    fn removed(&self) -> usize {
+        self.stats.iter().map(|s| s.removed).sum()
+    }
+}
+
+pub async fn execute(sessions: Vec<String>, test: Option<String>, summary: bool) -> Result<()> {
+    info!("Comparing sessions: {}", sessions.join(", "));
+
+    if sessions.len() < 2 {
+        bail!("Need at least two sessions to compare");
+    }
+
+    let manager = SessionManager::open()?;
+    let mut entries = Vec::new();
+
+    for name in &sessions {
+        let session = manager
+            .get_session(name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Session '{}' not found", name))?;
+        let worktree = manager.git_worktree(&session)?.to_path_buf();
+        let base = session
+            .base_commit
+            .clone()
+            .with_context(|| format!("Session '{}' has no base commit", name))?;
+
+        let tree = checkpoint::snapshot_tree(&worktree)?;
+        let stats = diff::numstat(&worktree, &base, &tree)?;
+
+        entries.push(Entry {
+            session,
+            worktree,
+            tree,
+            stats,
+        });
+    }
+
+    let base = entries[0].session.base_commit.clone().unwrap_or_default();
+    if let Some(other) = entries
+        .iter()
+        .find(|e| e.session.base_commit.as_deref() != Some(base.as_str()))
+    {
+        bail!(
+            "Session '{}' started from a different base commit than '{}'",
+            other.session.display_name(),
+            entries[0].session.display_name()
+        );
+    }
+
+    let runs = match &test {
+        Some(command) => run_tests(&entries, &manager, command).await?,
+        None => vec![],
+    };
+
+    println!("Comparison (base {}):", &base[..base.len().min(8)]);
+    println!("==========\n");
+    println!(
+        "{:<12} {:<10} {:>6} {:>8} {:>8}  {:<10}",
+        "Session", "Agent", "Files", "Added", "Removed", "Tests"
+    );
+    println!("{}", "-".repeat(60));
+
+    for (i, entry) in entries.iter().enumerate() {
+        println!(
+            "{:<12} {:<10} {:>6} {:>8} {:>8}  {:<10}",
+            entry.session.display_name(),
+            entry.session.agent,
+            entry.stats.len(),
+            format!("+{}", entry.added()),
+            format!("-{}", entry.removed()),
+            runs.get(i).map(|r| r.result.as_str()).unwrap_or("-")
+        );
+    }
+
+    for (entry, run) in entries.iter().zip(&runs).filter(|(_, r)| !r.passed) {
+        println!(
+            "\n❌ Tests {} in '{}' (full output: {})",
+            if run.result == "error" { "could not run" } else { "failed" },
+            entry.session.display_name(),
+            run.log.display()
+        );
+        for line in &run.tail {
+            println!("   {}", line);
+        }
+    }
+
+    if summary {
+        return Ok(());
+    }
+
+    // Which session touched which file
+    let paths: BTreeSet<&str> = entries
+        .iter()
+        .flat_map(|e| e.stats.iter().map(|s| s.path.as_str()))
+        .collect();
+
+    println!("\nFiles:");
+    let header: Vec<String> = entries
+        .iter()
+        .map(|e| format!("{:<12}", e.session.display_name()))
+        .collect();
+    println!("  {} Path", header.join(" "));
+    for path in paths {
+        let cells: Vec<String> = entries
+            .iter()
+            .map(|e| match e.stats.iter().find(|s| s.path == path) {
+                Some(s) => format!("{:<12}", format!("+{}/-{}", s.added, s.removed)),
+                None => format!("{:<12}", "·"),
+            })
+            .collect();
+        println!("  {} {}", cells.join(" "), path);
+    }
+
+    let color = std::io::stdout().is_terminal();
+    let first = &entries[0];
+    for other in &entries[1..] {
+        println!(
+            "\n=== {} → {} ===\n",
+            first.session.display_name(),
+            other.session.display_name()
+        );
+        let patch = diff::patch(&first.worktree, &first.tree, &other.tree, color)?;
+        if patch.is_empty() {
+            println!("(identical)");
+        } else {
+            println!("{}", patch);
+        }
+    }
+
+    Ok(())
+}
+
+/// Run the test command in every worktree concurrently, appending its
+/// output to each session's log
+async fn run_tests(entries: &[Entry], manager: &SessionManager, command: &str) -> Result<Vec<TestRun>> {
+    println!("🧪 Running '{}' in {} worktrees...\n", command, entries.len());
+
+    let mut handles = Vec::new();
+    for entry in entries {
+        let log = manager.log_path(&entry.session.id);
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log)
+            .with_context(|| format!("Failed to open log {}", log.display()))?;
+        writeln!(file, "$ {}", command)?;
+        let start = file.metadata()?.len();
+
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.arg("-c")
+            .arg(command)
+            .current_dir(&entry.worktree)
+            .stdin(Stdio::null())
+            .stdout(file.try_clone()?)
+            .stderr(file);
+        handles.push(tokio::spawn(async move {
+            let clock = Instant::now();
+            let status = cmd.status().await;
+            let secs = clock.elapsed().as_secs();
+            let (result, passed) = match &status {
+                Ok(s) if s.success() => (format!("pass {}s", secs), true),
+                Ok(_) => (format!("FAIL {}s", secs), false),
+                Err(_) => ("error".to_string(), false),
+            };
+            let tail = match status {
+                Ok(_) if passed => vec![],
+                Ok(_) => tail(&log, start),
+                Err(e) => vec![format!("Failed to run sh: {}", e)],
+            };
+            TestRun {
+                result,
+                passed,
+                tail,
+                log,
+            }
+        }));
+    }
+
+    let mut runs = Vec::new();
+    for handle in handles {
+        runs.push(handle.await?);
+    }
+
+    Ok(runs)
+}
+
+/// Last lines written to `log` after byte offset `start`
+fn tail(log: &Path, start: u64) -> Vec<String> {
+    let contents = fs::read(log).unwrap_or_default();
+    let output = String::from_utf8_lossy(contents.get(start as usize..).unwrap_or_default());
+    let lines: Vec<&str> = output.lines().collect();
+    lines[lines.len().saturating_sub(TAIL_LINES)..]
+        .iter()
+        .map(|l| l.to_string())
+        .collect()
+}
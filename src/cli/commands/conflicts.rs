@@ -0,0 +1,45 @@
+use anyhow::Result;
+use tracing::info;
+use crate::session::{overlap, SessionManager};
+
+pub async fn execute() -> Result<()> {
+    info!("Checking for overlapping edits");
+
+    let manager = SessionManager::open()?;
+    let sessions = manager.list_sessions();
+    let overlaps = overlap::detect(&sessions);
+
+    if overlaps.is_empty() {
+        println!("No overlapping edits between running sessions");
+        return Ok(());
+    }
+
+    let name = |id: &str| {
+        manager
+            .get_session(id)
+            .map(|s| s.display_name().to_string())
+            .unwrap_or_else(|| id.to_string())
+    };
+
+    println!("Overlapping Edits:");
+    println!("==================\n");
+
+    for o in &overlaps {
+        let kind = if o.is_hunk_level() { "same lines" } else { "same file" };
+        println!("⚠️  {} ({})", o.path, kind);
+        println!("   {} ↔ {}", name(&o.first), name(&o.second));
+        for (a, b) in &o.hunks {
+            println!(
+                "     lines {}-{} ↔ {}-{}",
+                a.start,
+                a.end - 1,
+                b.start,
+                b.end - 1
+            );
+        }
+    }
+
+    println!("\n💡 Use 'acta compare <a> <b>' to inspect the differences");
+
+    Ok(())
+}
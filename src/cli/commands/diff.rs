@@ -0,0 +1,27 @@
+use anyhow::Result;
+use std::io::IsTerminal;
+use tracing::info;
+use crate::session::SessionManager;
+
+pub async fn execute(session: String, stat: bool) -> Result<()> {
+    info!("Showing diff for session: {}", session);
+
+    let manager = SessionManager::open()?;
+    let session_info = manager
+        .get_session(&session)
+        .ok_or_else(|| anyhow::anyhow!("Session '{}' not found", session))?;
+
+    // Includes uncommitted and untracked work, not just commits
+    let color = std::io::stdout().is_terminal();
+    let diff = manager.isolation(session_info).diff(session_info, color)?;
+
+    let output = if stat { diff.stat } else { diff.patch };
+
+    if output.is_empty() {
+        println!("No changes in session '{}'", session);
+    } else {
+        println!("{}", output);
+    }
+
+    Ok(())
+}
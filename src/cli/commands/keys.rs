@@ -0,0 +1,32 @@
+use anyhow::Result;
+use tracing::info;
+use crate::config::keys::Action;
+use crate::config::Config;
+
+pub async fn list() -> Result<()> {
+    info!("Listing key bindings");
+
+    let config = Config::load()?;
+    let keymap = config.keymap()?;
+
+    println!("Key Bindings:");
+    println!("=============\n");
+
+    for (title, sequences) in [
+        ("Session list and panes", false),
+        ("Also while typing into a session", true),
+    ] {
+        println!("{}:", title);
+        for action in Action::ALL {
+            let keys = keymap.keys_for(*action, sequences);
+            if !keys.is_empty() {
+                println!("  {:<18} {:<18} {}", keys.join(", "), action.name(), action.description());
+            }
+        }
+        println!();
+    }
+
+    println!("💡 Change bindings under 'keys:' in {}", Config::get_config_path()?.display());
+
+    Ok(())
+}
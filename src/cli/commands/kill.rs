@@ -5,7 +5,7 @@ use crate::session::SessionManager;
 pub async fn execute(session: String, force: bool) -> Result<()> {
     info!("Killing session: {} (force: {})", session, force);
 
-    let mut manager = SessionManager::new()?;
+    let mut manager = SessionManager::open()?;
 
     // Check if session exists
     let session_info = manager
@@ -20,7 +20,7 @@ pub async fn execute(session: String, force: bool) -> Result<()> {
         println!("⚠️  Force kill enabled - skipping cleanup");
     }
 
-    manager.kill_session(&id)?;
+    manager.kill_session(&id, !force)?;
 
     println!("✅ Session terminated");
 
@@ -0,0 +1,68 @@
+use anyhow::Result;
+use tracing::info;
+use crate::session::SessionManager;
+use crate::tui::layout;
+
+pub async fn list() -> Result<()> {
+    info!("Listing saved layouts");
+
+    let names = layout::saved()?;
+
+    println!("Saved Layouts:");
+    println!("==============\n");
+
+    if names.is_empty() {
+        println!("No saved layouts");
+        return Ok(());
+    }
+
+    for name in names {
+        let panes = layout::load(&layout::named_path(&name)?)?
+            .map(|l| l.panes().len())
+            .unwrap_or(0);
+        println!("  {:<20} {} pane(s)", name, panes);
+    }
+
+    Ok(())
+}
+
+pub async fn save(name: String) -> Result<()> {
+    info!("Saving layout '{}'", name);
+
+    let current = layout::load(&layout::current_path()?)?
+        .ok_or_else(|| anyhow::anyhow!("No panes open; arrange sessions in 'acta tui' first"))?;
+    let path = layout::named_path(&name)?;
+    layout::save(&path, Some(&current))?;
+
+    println!("✅ Saved layout '{}' ({} pane(s))", name, current.panes().len());
+    println!("   {}", path.display());
+
+    Ok(())
+}
+
+pub async fn load(name: String) -> Result<()> {
+    info!("Loading layout '{}'", name);
+
+    let saved = layout::load(&layout::named_path(&name)?)?
+        .ok_or_else(|| anyhow::anyhow!("Layout '{}' not found", name))?;
+
+    let manager = SessionManager::open()?;
+    let missing: Vec<String> = saved
+        .panes()
+        .into_iter()
+        .filter(|id| manager.get_session(id).is_none())
+        .map(|id| id[..8.min(id.len())].to_string())
+        .collect();
+    let Some(restored) = saved.retain(|id| manager.get_session(id).is_some()) else {
+        anyhow::bail!("None of the sessions in layout '{}' exist anymore", name);
+    };
+    layout::save(&layout::current_path()?, Some(&restored))?;
+
+    println!("✅ Loaded layout '{}' ({} pane(s))", name, restored.panes().len());
+    if !missing.is_empty() {
+        println!("   Skipped sessions that no longer exist: {}", missing.join(", "));
+    }
+    println!("\n💡 A running 'acta tui' switches to it; otherwise it opens next time");
+
+    Ok(())
+}
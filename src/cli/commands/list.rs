@@ -1,11 +1,11 @@
 use anyhow::Result;
 use tracing::info;
-use crate::session::SessionManager;
+use crate::session::{overlap, SessionManager};
 
-pub async fn execute() -> Result<()> {
+pub async fn execute(check_overlaps: bool) -> Result<()> {
     info!("Listing active sessions");
 
-    let manager = SessionManager::new()?;
+    let manager = SessionManager::open()?;
     let sessions = manager.list_sessions();
 
     if sessions.is_empty() {
@@ -14,20 +14,48 @@ pub async fn execute() -> Result<()> {
         return Ok(());
     }
 
+    let overlaps = if check_overlaps {
+        overlap::detect(&sessions)
+    } else {
+        vec![]
+    };
+
     println!("Active Sessions:");
     println!("================\n");
-    println!("{:<8} {:<15} {:<10} {:<10}", "ID", "Agent", "Status", "Name");
+    println!(
+        "{:<8} {:<15} {:<10} {:<8} {:<10}",
+        "ID", "Agent", "Status", "Files", "Name"
+    );
     println!("{}", "-".repeat(60));
 
-    for session in sessions {
+    for session in &sessions {
         let short_id = &session.id[..8];
         let name = session.name.as_deref().unwrap_or("-");
+        let warning = if overlaps.iter().any(|o| o.involves(&session.id)) {
+            " ⚠"
+        } else {
+            ""
+        };
+        let files = match manager.isolation(session).status(session) {
+            Ok(status) if !status.exists => "missing".to_string(),
+            Ok(status) => status.changed_files.to_string(),
+            Err(_) => "-".to_string(),
+        };
         println!(
-            "{:<8} {:<15} {:<10} {:<10}",
+            "{:<8} {:<15} {:<10} {:<8} {:<10}{}",
             short_id,
             session.agent,
             format!("{:?}", session.status),
-            name
+            files,
+            name,
+            warning
+        );
+    }
+
+    if !overlaps.is_empty() {
+        println!(
+            "\n⚠️  {} overlapping edit(s) between sessions, see 'acta conflicts'",
+            overlaps.len()
         );
     }
 
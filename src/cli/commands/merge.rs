@@ -0,0 +1,30 @@
+use anyhow::{bail, Result};
+use tracing::info;
+use crate::git::isolation::MergeOutcome;
+use crate::session::SessionManager;
+
+pub async fn execute(session: String) -> Result<()> {
+    info!("Merging session: {}", session);
+
+    let manager = SessionManager::open()?;
+    let session_info = manager
+        .get_session(&session)
+        .ok_or_else(|| anyhow::anyhow!("Session '{}' not found", session))?;
+
+    println!("📥 Merging session '{}'...", session);
+    match manager.isolation(session_info).merge(session_info)? {
+        MergeOutcome::Merged { files, into } => {
+            println!("✅ Merged {} file(s) from session '{}' into {}", files, session, into);
+        }
+        MergeOutcome::NothingToMerge => println!("No changes to merge"),
+        MergeOutcome::Conflicts(files) => {
+            println!("⚠️  Merge would conflict in:");
+            for file in &files {
+                println!("   {}", file);
+            }
+            bail!("Merge aborted; run 'acta sync {}' first", session);
+        }
+    }
+
+    Ok(())
+}
@@ -1,7 +1,17 @@
 pub mod attach;
+pub mod checkpoint;
+pub mod compare;
+pub mod conflicts;
 pub mod config;
 pub mod detach;
+pub mod diff;
+pub mod keys;
 pub mod kill;
+pub mod layout;
 pub mod list;
+pub mod merge;
 pub mod new;
 pub mod plugin;
+pub mod rename;
+pub mod sync;
+pub mod waiting;
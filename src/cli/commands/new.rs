@@ -1,25 +1,57 @@
 use anyhow::Result;
 use tracing::info;
-use crate::session::SessionManager;
+use crate::config::Config;
+use crate::session::{NewSession, SessionManager, SessionStatus};
 
-pub async fn execute(agent: String, name: Option<String>, args: Vec<String>) -> Result<()> {
+pub async fn execute(options: NewSession) -> Result<()> {
+    let agent = options.agent.clone();
     info!(
         "Creating new {} session{}",
         agent,
-        name.as_ref()
+        options
+            .name
+            .as_ref()
             .map(|n| format!(" named '{}'", n))
             .unwrap_or_default()
     );
 
-    let mut manager = SessionManager::new()?;
-    let session = manager.create_session(agent.clone(), name.clone(), args)?;
+    let config = Config::load()?;
+    if config.get_plugin(&agent).is_none() {
+        anyhow::bail!(
+            "Unknown agent '{}'. Register it with: acta plugin register {} <command>",
+            agent,
+            agent
+        );
+    }
+
+    let mut manager = SessionManager::open()?;
+    let session = manager.create_session(&config, options, &mut |line| println!("   {}", line))?;
+
+    if session.status == SessionStatus::Failed {
+        anyhow::bail!(
+            "Setup failed for session {}, agent not started (log: {})",
+            session.id,
+            manager.log_path(&session.id).display()
+        );
+    }
 
     println!("✅ Created {} session", agent);
     println!("   ID: {}", session.id);
     if let Some(name) = &session.name {
         println!("   Name: {}", name);
     }
-    println!("   Worktree: {}", session.worktree_path.display());
+    println!("   Worktree: {} ({})", session.worktree_path.display(), session.backend);
+    match &session.branch {
+        Some(branch) => println!("   Branch: {}", branch),
+        None if session.repo_path.is_some() => println!("   Branch: (detached)"),
+        None => {}
+    }
+    if !session.sparse.is_empty() {
+        println!("   Sparse: {}", session.sparse.join(", "));
+    }
+    if !session.tags.is_empty() {
+        println!("   Tags: {}", session.tags.join(", "));
+    }
     println!("   Status: {:?}", session.status);
 
     println!("\n💡 Session created! Use 'acta attach {}' to connect", session.id);
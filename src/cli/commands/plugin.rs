@@ -40,6 +40,7 @@ pub async fn register(name: String, command: String) -> Result<()> {
         command: command.clone(),
         args: vec![],
         env: HashMap::new(),
+        git_identity: None,
     };
 
     config.register_plugin(name.clone(), plugin);
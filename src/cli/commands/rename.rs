@@ -0,0 +1,14 @@
+use anyhow::Result;
+use tracing::info;
+use crate::session::SessionManager;
+
+pub async fn execute(session: String, name: String) -> Result<()> {
+    info!("Renaming session {} to {}", session, name);
+
+    let mut manager = SessionManager::open()?;
+    let renamed = manager.rename_session(&session, &name)?;
+
+    println!("✅ Renamed session {} to '{}'", &renamed.id[..8], name);
+
+    Ok(())
+}
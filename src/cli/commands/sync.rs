@@ -0,0 +1,114 @@
+use anyhow::{bail, Context, Result};
+use tracing::info;
+use crate::git::{self, sync::{self, Outcome, Strategy}};
+use crate::session::{Session, SessionManager, SessionStatus};
+
+/// Start of the conflict notice `--notify` queues for the agent
+const NOTICE: &str = "acta: syncing with ";
+
+pub enum Action {
+    Sync(Strategy),
+    Continue,
+    Abort,
+}
+
+pub async fn execute(
+    session: String,
+    action: Action,
+    base: Option<String>,
+    notify: bool,
+) -> Result<()> {
+    info!("Syncing session: {}", session);
+
+    let mut manager = SessionManager::open()?;
+    let session_info = manager
+        .get_session(&session)
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Session '{}' not found", session))?;
+    let worktree = manager.git_worktree(&session_info)?;
+
+    let base = base
+        .or_else(|| session_info.base_branch.clone())
+        .context("Session has no base branch, pass one with --base")?;
+
+    // What the session goes back to once the sync is through
+    let previous = match &session_info.status {
+        SessionStatus::Conflicted => session_info.status_before_sync.clone().unwrap_or(SessionStatus::Running),
+        status => status.clone(),
+    };
+    let settle = |s: &mut Session| {
+        s.status = previous.clone();
+        s.status_before_sync = None;
+    };
+
+    let outcome = match action {
+        Action::Sync(strategy) => {
+            println!("🔄 Syncing '{}' with {} ({})...", session, base, strategy);
+            sync::sync(worktree, &base, strategy)?
+        }
+        Action::Continue => {
+            manager.unqueue_messages(&session_info.id, |m| m.starts_with(NOTICE))?;
+            sync::resume(worktree)?
+        }
+        Action::Abort => {
+            sync::abort(worktree)?;
+            manager.unqueue_messages(&session_info.id, |m| m.starts_with(NOTICE))?;
+            manager.update_session(&session_info.id, settle)?;
+            println!("↩️  Aborted sync of '{}'", session);
+            return Ok(());
+        }
+    };
+
+    match outcome {
+        Outcome::UpToDate => {
+            println!("✅ Already up to date with {}", base);
+            if session_info.status == SessionStatus::Conflicted {
+                manager.update_session(&session_info.id, settle)?;
+            }
+        }
+        Outcome::Updated => {
+            let commit = git::rev_parse(worktree, &base)?;
+            manager.update_session(&session_info.id, |s| {
+                s.base_commit = Some(commit);
+                s.base_branch = Some(base.clone());
+                settle(s);
+            })?;
+            println!("✅ Session '{}' is up to date with {}", session, base);
+        }
+        Outcome::Conflicted(files) => {
+            manager.update_session(&session_info.id, |s| {
+                s.status_before_sync = Some(previous.clone());
+                s.status = SessionStatus::Conflicted;
+            })?;
+
+            println!("⚠️  Conflicts in {} file(s):", files.len());
+            for file in &files {
+                println!("   {}", file);
+            }
+            println!("\n   Worktree: {}", worktree.display());
+            println!("💡 Resolve them, then run 'acta sync {} --continue' (or --abort)", session);
+
+            // Only an agent hosted by a running acta process picks up queued
+            // messages; a notice left for any other would arrive long after
+            // the fact. Notices left behind are withdrawn by --continue or
+            // --abort.
+            let live = manager.is_hosted(&session_info.id);
+            if notify && live {
+                let message = format!(
+                    "{}{} stopped on conflicts in {}. Please resolve the conflict markers and stage the files.",
+                    NOTICE,
+                    base,
+                    files.join(", ")
+                );
+                manager.queue_message(&session_info.id, &message)?;
+                println!("📨 Queued a message for the agent");
+            } else if notify {
+                println!("📭 Not notifying the agent, no acta process is running it for '{}'", session);
+            }
+
+            bail!("Sync stopped on conflicts");
+        }
+    }
+
+    Ok(())
+}
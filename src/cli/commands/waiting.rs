@@ -0,0 +1,29 @@
+use anyhow::{Context, Result};
+use tracing::info;
+use crate::session::{SessionManager, SessionStatus};
+
+pub async fn execute(session: Option<String>, done: bool) -> Result<()> {
+    let session = match session {
+        Some(session) => session,
+        None => std::env::var("ACTA_SESSION").context("No session given and $ACTA_SESSION is not set")?,
+    };
+    info!("Marking session {} as {}", session, if done { "running" } else { "waiting" });
+
+    let mut manager = SessionManager::open()?;
+    let session_info = manager
+        .get_session(&session)
+        .ok_or_else(|| anyhow::anyhow!("Session '{}' not found", session))?;
+    let (id, name) = (session_info.id.clone(), session_info.display_name().to_string());
+
+    if done {
+        if session_info.status == SessionStatus::NeedsInput {
+            manager.update_status(&id, SessionStatus::Running)?;
+        }
+        println!("▶️  Session '{}' is running", name);
+    } else {
+        manager.update_status(&id, SessionStatus::NeedsInput)?;
+        println!("⏳ Session '{}' is waiting for input", name);
+    }
+
+    Ok(())
+}
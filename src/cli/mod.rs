@@ -2,6 +2,9 @@ mod commands;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use crate::git::sync::Strategy;
+use crate::git::isolation::Checkout;
+use crate::session::NewSession;
 
 /// Acta - A terminal multiplexer for agentic coding
 #[derive(Parser, Debug)]
@@ -27,6 +30,34 @@ enum Commands {
         #[arg(short, long)]
         name: Option<String>,
 
+        /// Check out an existing local or remote-tracking branch
+        #[arg(short, long, conflicts_with = "detach")]
+        branch: Option<String>,
+
+        /// Check out a commit on a detached HEAD
+        #[arg(long, value_name = "COMMIT")]
+        detach: Option<String>,
+
+        /// Start the session branch from this branch instead of the current one
+        #[arg(long, conflicts_with_all = ["branch", "detach"])]
+        base: Option<String>,
+
+        /// Let the agent push and update protected refs
+        #[arg(long)]
+        allow_push: bool,
+
+        /// Only check out these directories (cone mode, repeatable)
+        #[arg(long, value_name = "PATH")]
+        sparse: Vec<String>,
+
+        /// Only check out the directories of a sparse profile from the config
+        #[arg(long, value_name = "NAME")]
+        sparse_profile: Option<String>,
+
+        /// Label the session, e.g. to filter for it in the TUI (repeatable)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+
         /// Additional arguments to pass to the agent
         #[arg(last = true)]
         args: Vec<String>,
@@ -34,7 +65,12 @@ enum Commands {
 
     /// List active sessions
     #[command(alias = "ls")]
-    List,
+    List {
+        /// Also check for overlapping edits, which snapshots every live
+        /// worktree
+        #[arg(long)]
+        overlaps: bool,
+    },
 
     /// Open interactive TUI
     Tui,
@@ -48,6 +84,26 @@ enum Commands {
     /// Detach from current session
     Detach,
 
+    /// Mark a session as waiting for input, e.g. from an agent's
+    /// notification hook; the TUI queues it for attention
+    Waiting {
+        /// Session ID or name (defaults to $ACTA_SESSION, which agents
+        /// started by acta have)
+        session: Option<String>,
+
+        /// The agent is working again
+        #[arg(long)]
+        done: bool,
+    },
+
+    /// Rename a session
+    Rename {
+        /// Session ID or name
+        session: String,
+        /// New name
+        name: String,
+    },
+
     /// Kill a session
     Kill {
         /// Session ID or name
@@ -58,12 +114,114 @@ enum Commands {
         force: bool,
     },
 
+    /// Snapshot a session's worktree into a checkpoint
+    Checkpoint {
+        /// Session ID or name
+        session: String,
+
+        /// Optional note stored with the checkpoint
+        #[arg(short, long)]
+        message: Option<String>,
+
+        /// Keep running and checkpoint on idle/interval per config
+        #[arg(short, long, conflicts_with = "message")]
+        watch: bool,
+    },
+
+    /// List checkpoints of a session
+    Checkpoints {
+        /// Session ID or name
+        session: String,
+    },
+
+    /// Restore a session's worktree to a checkpoint
+    Rollback {
+        /// Session ID or name
+        session: String,
+
+        /// Checkpoint number
+        checkpoint: u32,
+    },
+
+    /// Compare the worktrees of sessions started from the same base
+    Compare {
+        /// Session IDs or names (at least two)
+        #[arg(required = true, num_args = 2..)]
+        sessions: Vec<String>,
+
+        /// Command to run in each worktree (e.g. "cargo test")
+        #[arg(short, long)]
+        test: Option<String>,
+
+        /// Only print the summary table
+        #[arg(short, long)]
+        summary: bool,
+    },
+
+    /// List overlapping edits between running sessions
+    Conflicts,
+
+    /// Show a session's changes against its base
+    Diff {
+        /// Session ID or name
+        session: String,
+
+        /// Only show a diffstat
+        #[arg(long)]
+        stat: bool,
+    },
+
+    /// Merge a session's work back (branch merge, or apply-back for copies)
+    Merge {
+        /// Session ID or name
+        session: String,
+    },
+
+    /// Update a session branch from its base branch
+    Sync {
+        /// Session ID or name
+        session: String,
+
+        /// Merge the base into the session branch
+        #[arg(long, conflicts_with = "rebase")]
+        merge: bool,
+
+        /// Rebase the session branch onto the base (default)
+        #[arg(long)]
+        rebase: bool,
+
+        /// Base branch to sync with (defaults to the one the session started from)
+        #[arg(long)]
+        base: Option<String>,
+
+        /// Tell the agent about conflicts when the session is live
+        #[arg(long)]
+        notify: bool,
+
+        /// Finish a sync after resolving conflicts
+        #[arg(long = "continue", conflicts_with_all = ["merge", "rebase", "abort"])]
+        resume: bool,
+
+        /// Abandon a sync that stopped on conflicts
+        #[arg(long, conflicts_with_all = ["merge", "rebase"])]
+        abort: bool,
+    },
+
     /// Manage configuration
     Config {
         #[command(subcommand)]
         command: ConfigCommands,
     },
 
+    /// List the TUI key bindings
+    Keys,
+
+    /// Save and restore TUI pane layouts
+    Layout {
+        #[command(subcommand)]
+        command: LayoutCommands,
+    },
+
     /// Manage plugins
     Plugin {
         #[command(subcommand)]
@@ -94,6 +252,24 @@ enum ConfigCommands {
     Path,
 }
 
+#[derive(Subcommand, Debug)]
+enum LayoutCommands {
+    /// List saved layouts
+    List,
+
+    /// Save the TUI's current panes under a name
+    Save {
+        /// Layout name
+        name: String,
+    },
+
+    /// Restore a saved layout in the TUI
+    Load {
+        /// Layout name
+        name: String,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 enum PluginCommands {
     /// List available plugins
@@ -117,20 +293,101 @@ enum PluginCommands {
 impl Cli {
     pub async fn execute(self) -> Result<()> {
         match self.command {
-            Commands::New { agent, name, args } => {
-                commands::new::execute(agent, name, args).await
+            Commands::New {
+                agent,
+                name,
+                branch,
+                detach,
+                base,
+                allow_push,
+                sparse,
+                sparse_profile,
+                tags,
+                args,
+            } => {
+                let checkout = match (branch, detach, base) {
+                    (Some(branch), _, _) => Checkout::Branch(branch),
+                    (None, Some(commit), _) => Checkout::Detached(commit),
+                    (None, None, Some(base)) => Checkout::Base(base),
+                    (None, None, None) => Checkout::NewBranch,
+                };
+                let options = NewSession {
+                    agent,
+                    name,
+                    args,
+                    checkout,
+                    backend: None,
+                    allow_push,
+                    sparse,
+                    sparse_profile,
+                    tags,
+                    id: None,
+                };
+                commands::new::execute(options).await
             }
-            Commands::List => commands::list::execute().await,
+            Commands::List { overlaps } => commands::list::execute(overlaps).await,
             Commands::Tui => crate::tui::run().await,
             Commands::Attach { session } => commands::attach::execute(session).await,
             Commands::Detach => commands::detach::execute().await,
+            Commands::Waiting { session, done } => commands::waiting::execute(session, done).await,
+            Commands::Rename { session, name } => commands::rename::execute(session, name).await,
             Commands::Kill { session, force } => commands::kill::execute(session, force).await,
+            Commands::Checkpoint {
+                session,
+                message,
+                watch,
+            } => {
+                if watch {
+                    commands::checkpoint::watch(session).await
+                } else {
+                    commands::checkpoint::create(session, message).await
+                }
+            }
+            Commands::Checkpoints { session } => commands::checkpoint::list(session).await,
+            Commands::Rollback {
+                session,
+                checkpoint,
+            } => commands::checkpoint::rollback(session, checkpoint).await,
+            Commands::Compare {
+                sessions,
+                test,
+                summary,
+            } => commands::compare::execute(sessions, test, summary).await,
+            Commands::Conflicts => commands::conflicts::execute().await,
+            Commands::Diff { session, stat } => commands::diff::execute(session, stat).await,
+            Commands::Merge { session } => commands::merge::execute(session).await,
+            Commands::Sync {
+                session,
+                merge,
+                rebase: _,
+                base,
+                notify,
+                resume,
+                abort,
+            } => {
+                let action = if abort {
+                    commands::sync::Action::Abort
+                } else if resume {
+                    commands::sync::Action::Continue
+                } else if merge {
+                    commands::sync::Action::Sync(Strategy::Merge)
+                } else {
+                    commands::sync::Action::Sync(Strategy::Rebase)
+                };
+                commands::sync::execute(session, action, base, notify).await
+            }
             Commands::Config { command } => match command {
                 ConfigCommands::List => commands::config::list().await,
                 ConfigCommands::Get { key } => commands::config::get(key).await,
                 ConfigCommands::Set { key, value } => commands::config::set(key, value).await,
                 ConfigCommands::Path => commands::config::path().await,
             },
+            Commands::Keys => commands::keys::list().await,
+            Commands::Layout { command } => match command {
+                LayoutCommands::List => commands::layout::list().await,
+                LayoutCommands::Save { name } => commands::layout::save(name).await,
+                LayoutCommands::Load { name } => commands::layout::load(name).await,
+            },
             Commands::Plugin { command } => match command {
                 PluginCommands::List => commands::plugin::list().await,
                 PluginCommands::Register { name, command } => {
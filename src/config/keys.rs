@@ -0,0 +1,544 @@
+// TUI key bindings: chords written the way they appear in config (`C-b`,
+// `M-x`, `Enter`, `|`), sequences of them (`C-b d`) and the actions they run
+
+use anyhow::{bail, Context, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl From<KeyEvent> for Chord {
+    fn from(key: KeyEvent) -> Self {
+        let mut modifiers = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        let code = match key.code {
+            // Shift is already part of the character (`|`, `A`) and of BackTab
+            KeyCode::Char(c) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                if modifiers.contains(KeyModifiers::CONTROL) {
+                    KeyCode::Char(c.to_ascii_lowercase())
+                } else {
+                    KeyCode::Char(c)
+                }
+            }
+            KeyCode::BackTab => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::BackTab
+            }
+            code => code,
+        };
+        Self { code, modifiers }
+    }
+}
+
+impl FromStr for Chord {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        while rest.len() > 2 && rest.as_bytes()[1] == b'-' {
+            match rest.as_bytes()[0] {
+                b'C' => modifiers |= KeyModifiers::CONTROL,
+                b'M' => modifiers |= KeyModifiers::ALT,
+                b'S' => modifiers |= KeyModifiers::SHIFT,
+                _ => break,
+            }
+            rest = &rest[2..];
+        }
+
+        let code = match rest.to_ascii_lowercase().as_str() {
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            // Terminals send Shift-Tab as BackTab
+            "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "space" => KeyCode::Char(' '),
+            "backspace" => KeyCode::Backspace,
+            "up" | "↑" => KeyCode::Up,
+            "down" | "↓" => KeyCode::Down,
+            "left" | "←" => KeyCode::Left,
+            "right" | "→" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "delete" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            name => match (name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()), rest.chars().count()) {
+                (Some(n @ 1..=12), _) => KeyCode::F(n),
+                (_, 1) => {
+                    let c = rest.chars().next().unwrap_or_default();
+                    if modifiers.contains(KeyModifiers::CONTROL) {
+                        KeyCode::Char(c.to_ascii_lowercase())
+                    } else {
+                        KeyCode::Char(c)
+                    }
+                }
+                _ => bail!("Unknown key '{}'", s),
+            },
+        };
+
+        // Shift is already part of the character, and terminals don't report
+        // it next to Control, so these would silently bind the unshifted key
+        if let (KeyCode::Char(c), true) = (code, modifiers.contains(KeyModifiers::SHIFT)) {
+            if modifiers.contains(KeyModifiers::CONTROL) {
+                bail!("Invalid key '{}': terminals cannot tell Shift apart with Control on character keys", s);
+            }
+            let alt = if modifiers.contains(KeyModifiers::ALT) { "M-" } else { "" };
+            if c.is_lowercase() {
+                bail!("Invalid key '{}': write '{}{}' for the shifted key", s, alt, c.to_uppercase());
+            }
+            bail!("Invalid key '{}': write the shifted character itself, without S-", s);
+        }
+
+        Ok(Chord::from(KeyEvent::new(code, modifiers)))
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, prefix) in [
+            (KeyModifiers::CONTROL, "C-"),
+            (KeyModifiers::ALT, "M-"),
+            (KeyModifiers::SHIFT, "S-"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(prefix)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::Up => f.write_str("↑"),
+            KeyCode::Down => f.write_str("↓"),
+            KeyCode::Left => f.write_str("←"),
+            KeyCode::Right => f.write_str("→"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Back,
+    Quit,
+    Next,
+    Prev,
+    Attach,
+    Detach,
+    Pane,
+    Panes,
+    SplitHorizontal,
+    SplitVertical,
+    Zoom,
+    Close,
+    Layout,
+    New,
+    Rename,
+    Kill,
+    Filter,
+    Sort,
+    NextWaiting,
+    Diff,
+    NextFile,
+    PrevFile,
+    NextHunk,
+    PrevHunk,
+    PageDown,
+    PageUp,
+    CopyMode,
+    SendPrefix,
+}
+
+impl Action {
+    pub const ALL: &'static [Action] = &[
+        Action::Back,
+        Action::Quit,
+        Action::Next,
+        Action::Prev,
+        Action::Attach,
+        Action::Detach,
+        Action::Pane,
+        Action::Panes,
+        Action::SplitHorizontal,
+        Action::SplitVertical,
+        Action::Zoom,
+        Action::Close,
+        Action::Layout,
+        Action::New,
+        Action::Rename,
+        Action::Kill,
+        Action::Filter,
+        Action::Sort,
+        Action::NextWaiting,
+        Action::Diff,
+        Action::NextFile,
+        Action::PrevFile,
+        Action::NextHunk,
+        Action::PrevHunk,
+        Action::PageDown,
+        Action::PageUp,
+        Action::CopyMode,
+        Action::SendPrefix,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Back => "back",
+            Action::Quit => "quit",
+            Action::Next => "next",
+            Action::Prev => "prev",
+            Action::Attach => "attach",
+            Action::Detach => "detach",
+            Action::Pane => "pane",
+            Action::Panes => "panes",
+            Action::SplitHorizontal => "split-horizontal",
+            Action::SplitVertical => "split-vertical",
+            Action::Zoom => "zoom",
+            Action::Close => "close",
+            Action::Layout => "layout",
+            Action::New => "new",
+            Action::Rename => "rename",
+            Action::Kill => "kill",
+            Action::Filter => "filter",
+            Action::Sort => "sort",
+            Action::NextWaiting => "next-waiting",
+            Action::Diff => "diff",
+            Action::NextFile => "next-file",
+            Action::PrevFile => "prev-file",
+            Action::NextHunk => "next-hunk",
+            Action::PrevHunk => "prev-hunk",
+            Action::PageDown => "page-down",
+            Action::PageUp => "page-up",
+            Action::CopyMode => "copy-mode",
+            Action::SendPrefix => "send-prefix",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Back => "Leave the current view; quits from the session list",
+            Action::Quit => "Exit the TUI",
+            Action::Next => "Next session, next pane, or scroll the diff down",
+            Action::Prev => "Previous session, previous pane, or scroll the diff up",
+            Action::Attach => "Type into a session: full screen from the list, in place in a pane",
+            Action::Detach => "Stop typing into the session",
+            Action::Pane => "Show the selected session in a pane",
+            Action::Panes => "Switch between the session list and the panes",
+            Action::SplitHorizontal => "Split the focused pane side by side",
+            Action::SplitVertical => "Split the focused pane, stacked",
+            Action::Zoom => "Zoom the focused pane to the full screen and back",
+            Action::Close => "Close the focused pane; the agent keeps running",
+            Action::Layout => "Rearrange the panes with the next layout preset",
+            Action::New => "Create a session and show it",
+            Action::Rename => "Rename the session",
+            Action::Kill => "Kill the session, optionally keeping its worktree",
+            Action::Filter => "Fuzzy-filter the session list by name, agent, branch, tag or status",
+            Action::Sort => "Sort the session list by creation, last activity, agent or status",
+            Action::NextWaiting => "Go to the next session waiting for input, oldest first",
+            Action::Diff => "Review the session's changes against its base; again to refresh",
+            Action::NextFile => "Next file in the diff",
+            Action::PrevFile => "Previous file in the diff",
+            Action::NextHunk => "Next hunk in the diff, moving on to the next file",
+            Action::PrevHunk => "Previous hunk in the diff, moving back to the previous file",
+            Action::PageDown => "Scroll the diff down a page",
+            Action::PageUp => "Scroll the diff up a page",
+            Action::CopyMode => "Scroll back, search and copy text in the focused pane",
+            Action::SendPrefix => "Send the first key of the sequence to the agent",
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match Action::ALL.iter().find(|a| a.name() == s) {
+            Some(action) => Ok(*action),
+            None => bail!(
+                "Unknown action '{}' (expected one of: {}, or none)",
+                s,
+                Action::ALL.iter().map(|a| a.name()).collect::<Vec<_>>().join(", ")
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub keys: Vec<Chord>,
+    pub action: Action,
+}
+
+impl Binding {
+    /// Sequences of more than one chord also work while typing into an agent
+    pub fn is_sequence(&self) -> bool {
+        self.keys.len() > 1
+    }
+
+    pub fn keys_text(&self) -> String {
+        self.keys.iter().map(Chord::to_string).collect::<Vec<_>>().join(" ")
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Lookup {
+    Action(Action),
+    /// The keys so far start a longer sequence
+    Pending,
+    Unbound,
+}
+
+/// Default bindings with the user's `keys:` applied on top
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+impl Keymap {
+    /// `overrides` maps key sequences to action names; `none` removes a default
+    pub fn new(prefix: &str, overrides: &HashMap<String, String>) -> Result<Self> {
+        let prefix: Chord = prefix
+            .parse()
+            .with_context(|| format!("Invalid tui.prefix '{}'", prefix))?;
+        let mut bindings = defaults(prefix);
+
+        let mut overrides: Vec<_> = overrides.iter().collect();
+        overrides.sort();
+        for (keys, action) in overrides {
+            let sequence = keys
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<Vec<Chord>>>()
+                .with_context(|| format!("Invalid key binding '{}'", keys))?;
+            if sequence.is_empty() {
+                bail!("Empty key binding for '{}'", action);
+            }
+            bindings.retain(|b| b.keys != sequence);
+            if action != "none" {
+                let action = action
+                    .parse()
+                    .with_context(|| format!("Invalid key binding '{}'", keys))?;
+                bindings.push(Binding {
+                    keys: sequence,
+                    action,
+                });
+            }
+        }
+
+        for short in &bindings {
+            if let Some(long) = bindings
+                .iter()
+                .find(|b| b.keys.len() > short.keys.len() && b.keys.starts_with(&short.keys))
+            {
+                bail!(
+                    "Key binding '{}' ({}) makes '{}' ({}) unreachable",
+                    short.keys_text(),
+                    short.action.name(),
+                    long.keys_text(),
+                    long.action.name()
+                );
+            }
+        }
+
+        Ok(Self { bindings })
+    }
+
+    /// Action for the keys pressed so far. While typing only sequences count,
+    /// single keys belong to the agent.
+    pub fn lookup(&self, keys: &[Chord], typing: bool) -> Lookup {
+        let mut pending = false;
+        for binding in self.bindings.iter().filter(|b| !typing || b.is_sequence()) {
+            if binding.keys == keys {
+                return Lookup::Action(binding.action);
+            }
+            pending |= binding.keys.starts_with(keys);
+        }
+        if pending {
+            Lookup::Pending
+        } else {
+            Lookup::Unbound
+        }
+    }
+
+    /// Bindings that complete the keys pressed so far
+    pub fn continuations<'a>(&'a self, keys: &'a [Chord]) -> impl Iterator<Item = &'a Binding> {
+        self.bindings
+            .iter()
+            .filter(move |b| b.keys.len() > keys.len() && b.keys.starts_with(keys))
+    }
+
+    /// Keys bound to `action`, single keys or sequences
+    pub fn keys_for(&self, action: Action, sequences: bool) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|b| b.action == action && b.is_sequence() == sequences)
+            .map(Binding::keys_text)
+            .collect()
+    }
+}
+
+fn defaults(prefix: Chord) -> Vec<Binding> {
+    let single = [
+        ("q", Action::Back),
+        ("Esc", Action::Back),
+        ("j", Action::Next),
+        ("Down", Action::Next),
+        ("l", Action::Next),
+        ("Right", Action::Next),
+        ("k", Action::Prev),
+        ("Up", Action::Prev),
+        ("h", Action::Prev),
+        ("Left", Action::Prev),
+        ("Enter", Action::Attach),
+        ("i", Action::Attach),
+        ("t", Action::Pane),
+        ("Tab", Action::Panes),
+        ("|", Action::SplitHorizontal),
+        ("-", Action::SplitVertical),
+        ("z", Action::Zoom),
+        ("x", Action::Close),
+        ("Space", Action::Layout),
+        ("n", Action::New),
+        ("r", Action::Rename),
+        ("X", Action::Kill),
+        ("/", Action::Filter),
+        ("s", Action::Sort),
+        ("w", Action::NextWaiting),
+        ("d", Action::Diff),
+        ("J", Action::NextFile),
+        ("K", Action::PrevFile),
+        ("]", Action::NextHunk),
+        ("[", Action::PrevHunk),
+        ("PageDown", Action::PageDown),
+        ("C-d", Action::PageDown),
+        ("PageUp", Action::PageUp),
+        ("C-u", Action::PageUp),
+        ("c", Action::CopyMode),
+    ];
+    let prefixed = [
+        ("d", Action::Detach),
+        ("n", Action::Next),
+        ("p", Action::Prev),
+        ("c", Action::New),
+        (",", Action::Rename),
+        ("X", Action::Kill),
+        ("w", Action::NextWaiting),
+        ("z", Action::Zoom),
+        ("Space", Action::Layout),
+        ("[", Action::CopyMode),
+    ];
+
+    let mut bindings: Vec<Binding> = single
+        .iter()
+        .chain(&prefixed)
+        .map(|(key, action)| (key.parse::<Chord>().expect("valid default key"), *action))
+        .enumerate()
+        .map(|(i, (chord, action))| Binding {
+            keys: if i < single.len() { vec![chord] } else { vec![prefix, chord] },
+            action,
+        })
+        .collect();
+    bindings.push(Binding {
+        keys: vec![prefix, prefix],
+        action: Action::SendPrefix,
+    });
+    bindings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(s: &str) -> Chord {
+        s.parse().unwrap()
+    }
+
+    fn keys(s: &str) -> Vec<Chord> {
+        s.split_whitespace().map(chord).collect()
+    }
+
+    fn keymap(overrides: &[(&str, &str)]) -> Result<Keymap> {
+        let overrides = overrides.iter().map(|(k, a)| (k.to_string(), a.to_string())).collect();
+        Keymap::new("C-b", &overrides)
+    }
+
+    #[test]
+    fn chords_round_trip_through_display() {
+        for key in [
+            "C-b", "M-x", "C-M-a", "Enter", "Esc", "Tab", "BackTab", "Space", "|", "X", "F5", "Up", "PageDown", "S-Up",
+            "M-A", "C-Left",
+        ] {
+            let parsed = chord(key);
+            assert_eq!(chord(&parsed.to_string()), parsed, "{} shown as {}", key, parsed);
+        }
+        assert_eq!(chord("C-b").to_string(), "C-b");
+        assert_eq!(chord("up").to_string(), "↑");
+        assert_eq!(chord("enter").to_string(), "Enter");
+    }
+
+    #[test]
+    fn chords_match_key_events() {
+        let event = |code, modifiers| Chord::from(KeyEvent::new(code, modifiers));
+        assert_eq!(event(KeyCode::Char('A'), KeyModifiers::SHIFT), chord("A"));
+        assert_eq!(event(KeyCode::Char('|'), KeyModifiers::SHIFT), chord("|"));
+        assert_eq!(event(KeyCode::Char('B'), KeyModifiers::CONTROL), chord("C-b"));
+        assert_eq!(chord("C-B"), chord("C-b"));
+        assert_eq!(event(KeyCode::BackTab, KeyModifiers::SHIFT), chord("S-Tab"));
+        assert_eq!(event(KeyCode::Up, KeyModifiers::SHIFT), chord("S-Up"));
+    }
+
+    #[test]
+    fn shift_on_characters_is_rejected() {
+        let error = |key: &str| format!("{:#}", key.parse::<Chord>().unwrap_err());
+        assert!(error("S-a").contains("'A'"));
+        assert!(error("M-S-a").contains("'M-A'"));
+        assert!(error("S-1").contains("shifted character"));
+        assert!(error("C-S-x").contains("Control"));
+        assert!("Nope".parse::<Chord>().is_err());
+    }
+
+    #[test]
+    fn lookup_follows_sequences() {
+        let keymap = keymap(&[]).unwrap();
+        assert_eq!(keymap.lookup(&keys("j"), false), Lookup::Action(Action::Next));
+        assert_eq!(keymap.lookup(&keys("C-b"), false), Lookup::Pending);
+        assert_eq!(keymap.lookup(&keys("C-b d"), false), Lookup::Action(Action::Detach));
+        assert_eq!(keymap.lookup(&keys("C-b C-b"), false), Lookup::Action(Action::SendPrefix));
+        assert_eq!(keymap.lookup(&keys("C-b F5"), false), Lookup::Unbound);
+
+        // Single keys go to the agent while typing
+        assert_eq!(keymap.lookup(&keys("j"), true), Lookup::Unbound);
+        assert_eq!(keymap.lookup(&keys("C-b"), true), Lookup::Pending);
+        assert_eq!(keymap.lookup(&keys("C-b d"), true), Lookup::Action(Action::Detach));
+    }
+
+    #[test]
+    fn overrides_replace_and_remove_defaults() {
+        let keymap = keymap(&[("j", "quit"), ("q", "none"), ("g g", "next")]).unwrap();
+        assert_eq!(keymap.lookup(&keys("j"), false), Lookup::Action(Action::Quit));
+        assert_eq!(keymap.lookup(&keys("q"), false), Lookup::Unbound);
+        assert_eq!(keymap.lookup(&keys("g"), false), Lookup::Pending);
+        assert_eq!(keymap.lookup(&keys("g g"), false), Lookup::Action(Action::Next));
+        assert_eq!(keymap.keys_for(Action::Quit, false), ["j"]);
+    }
+
+    #[test]
+    fn unreachable_sequences_are_rejected() {
+        let error = |overrides: &[(&str, &str)]| format!("{:#}", keymap(overrides).unwrap_err());
+        assert!(error(&[("C-b", "quit")]).contains("unreachable"));
+        assert!(error(&[("g", "quit"), ("g g", "next")]).contains("'g' (quit) makes 'g g' (next) unreachable"));
+        assert!(keymap(&[("C-b", "quit"), ("C-b d", "none")]).is_err());
+        assert!(error(&[("j", "fly")]).contains("Unknown action 'fly'"));
+        assert!(error(&[("S-j", "next")]).contains("'J'"));
+        assert!(Keymap::new("S-b", &HashMap::new()).is_err());
+    }
+}
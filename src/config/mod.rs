@@ -1,8 +1,14 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+
+pub mod keys;
+pub mod theme;
+
+use keys::Keymap;
+use theme::{Theme, ThemeConfig};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -11,6 +17,165 @@ pub struct Config {
 
     #[serde(default)]
     pub settings: HashMap<String, String>,
+
+    #[serde(default)]
+    pub checkpoint: CheckpointConfig,
+
+    #[serde(default)]
+    pub worktree: WorktreeConfig,
+
+    /// Shell commands run in every new worktree before the agent starts
+    #[serde(default)]
+    pub setup: Vec<String>,
+
+    #[serde(default)]
+    pub guard: GuardConfig,
+
+    /// Named sets of cone-mode sparse checkout paths
+    #[serde(default)]
+    pub sparse: HashMap<String, Vec<String>>,
+
+    #[serde(default)]
+    pub tui: TuiConfig,
+
+    /// TUI key bindings: key sequence (`x`, `C-b d`) to action name
+    #[serde(default)]
+    pub keys: HashMap<String, String>,
+
+    /// TUI colors: a built-in theme and style slots overriding it
+    #[serde(default)]
+    pub theme: ThemeConfig,
+}
+
+/// Per-project overrides read from `.acta.yaml` at the repository root
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectConfig {
+    #[serde(default)]
+    pub worktree: WorktreeConfig,
+
+    /// Project setup commands, run after the global ones
+    #[serde(default)]
+    pub setup: Vec<String>,
+
+    #[serde(default)]
+    pub guard: GuardConfig,
+
+    /// Named sets of cone-mode sparse checkout paths
+    #[serde(default)]
+    pub sparse: HashMap<String, Vec<String>>,
+}
+
+/// Refs agents may not update from their worktree. Pushing is blocked by
+/// rewriting push URLs as well as by a hook; protected refs only by hooks,
+/// which an agent set on getting around them (e.g. by overriding
+/// `core.hooksPath`) can skip, so treat that part as a guardrail.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GuardConfig {
+    /// Branch names or full ref globs (e.g. `main`, `refs/heads/release/*`);
+    /// `main` and `master` when empty
+    #[serde(default)]
+    pub protected: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TuiConfig {
+    /// First key of the default sequences that work while typing into a
+    /// session (default `C-b`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+
+    /// Ring the terminal bell when a session starts waiting for input
+    #[serde(default)]
+    pub bell: bool,
+
+    /// File copy mode saves selections to (default `~/.acta/copy.txt`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub copy_file: Option<String>,
+}
+
+impl TuiConfig {
+    pub const DEFAULT_PREFIX: &'static str = "C-b";
+    pub const DEFAULT_COPY_FILE: &'static str = "~/.acta/copy.txt";
+
+    pub fn prefix(&self) -> &str {
+        self.prefix.as_deref().unwrap_or(Self::DEFAULT_PREFIX)
+    }
+
+    pub fn copy_file(&self) -> &str {
+        self.copy_file.as_deref().unwrap_or(Self::DEFAULT_COPY_FILE)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorktreeConfig {
+    /// Where session worktrees are created. Supports `~` and the
+    /// placeholders `{repo}`, `{name}`, `{id}`, `{short_id}` and `{agent}`;
+    /// relative paths are resolved against the repository root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+
+    /// Untracked or ignored files to bring into new worktrees
+    #[serde(default)]
+    pub include: Vec<IncludeRule>,
+
+    /// Globs skipped when copying non-git projects into a session
+    #[serde(default)]
+    pub ignore: Vec<String>,
+
+    /// How submodules are set up in new worktrees (default `shallow`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub submodules: Option<SubmoduleMode>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubmoduleMode {
+    /// Leave submodule directories empty
+    None,
+    /// Initialise the repository's own submodules
+    #[default]
+    Shallow,
+    /// Also initialise submodules nested inside them
+    Recursive,
+}
+
+impl WorktreeConfig {
+    pub const DEFAULT_ROOT: &'static str = "~/.acta/worktrees/{repo}/{name}";
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncludeRule {
+    /// Glob relative to the repository root (e.g. `.env`, `certs/*.pem`)
+    pub pattern: String,
+    #[serde(default)]
+    pub mode: IncludeMode,
+}
+
+impl IncludeRule {
+    /// Patterns may only reach into the repository: absolute ones or ones
+    /// with `..` would copy files from, and to, outside it
+    pub fn check(&self) -> Result<()> {
+        let path = Path::new(&self.pattern);
+        let inside = path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+        if !inside {
+            bail!(
+                "Include pattern '{}' must be relative to the repository root and not contain '..'",
+                self.pattern
+            );
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IncludeMode {
+    #[default]
+    Copy,
+    Symlink,
+    Reflink,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +185,50 @@ pub struct PluginConfig {
     pub args: Vec<String>,
     #[serde(default)]
     pub env: HashMap<String, String>,
+    /// Author and committer for commits the agent makes in its worktree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_identity: Option<GitIdentity>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitIdentity {
+    pub name: String,
+    pub email: String,
+    /// Append an `Acta-Session: <id>` trailer to every commit message
+    #[serde(default)]
+    pub trailer: bool,
+}
+
+/// When to snapshot session worktrees automatically
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointConfig {
+    /// Checkpoint once the agent (or, with `--watch`, the worktree) goes quiet
+    #[serde(default = "default_on_idle")]
+    pub on_idle: bool,
+    /// Seconds without activity before the agent counts as idle
+    #[serde(default = "default_idle_secs")]
+    pub idle_secs: u64,
+    /// Also checkpoint every N seconds while changes keep coming
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+}
+
+fn default_on_idle() -> bool {
+    true
+}
+
+fn default_idle_secs() -> u64 {
+    30
+}
+
+impl Default for CheckpointConfig {
+    fn default() -> Self {
+        Self {
+            on_idle: default_on_idle(),
+            idle_secs: default_idle_secs(),
+            interval_secs: None,
+        }
+    }
 }
 
 impl Default for Config {
@@ -33,6 +242,7 @@ impl Default for Config {
                 command: "claude".to_string(),
                 args: vec![],
                 env: HashMap::from([("ANTHROPIC_API_KEY".to_string(), "${ANTHROPIC_API_KEY}".to_string())]),
+                git_identity: None,
             },
         );
 
@@ -42,6 +252,7 @@ impl Default for Config {
                 command: "opencode".to_string(),
                 args: vec![],
                 env: HashMap::new(),
+                git_identity: None,
             },
         );
 
@@ -51,12 +262,21 @@ impl Default for Config {
                 command: "cursor".to_string(),
                 args: vec![],
                 env: HashMap::new(),
+                git_identity: None,
             },
         );
 
         Self {
             plugins,
             settings: HashMap::new(),
+            checkpoint: CheckpointConfig::default(),
+            worktree: WorktreeConfig::default(),
+            setup: vec![],
+            guard: GuardConfig::default(),
+            sparse: HashMap::new(),
+            tui: TuiConfig::default(),
+            keys: HashMap::new(),
+            theme: ThemeConfig::default(),
         }
     }
 }
@@ -77,6 +297,16 @@ impl Config {
 
         let config: Config = serde_yaml::from_str(&contents)
             .with_context(|| format!("Failed to parse config from {}", path.display()))?;
+        config
+            .keymap()
+            .with_context(|| format!("Invalid key bindings in {}", path.display()))?;
+        config
+            .theme()
+            .with_context(|| format!("Invalid theme in {}", path.display()))?;
+        for rule in &config.worktree.include {
+            rule.check()
+                .with_context(|| format!("Invalid worktree.include in {}", path.display()))?;
+        }
 
         Ok(config)
     }
@@ -107,6 +337,84 @@ impl Config {
         Ok(config_dir.join("config.yaml"))
     }
 
+    /// Include rules from the global config, with project rules for the same
+    /// pattern taking precedence
+    pub fn include_rules(&self, project: &ProjectConfig) -> Vec<IncludeRule> {
+        let mut rules: Vec<IncludeRule> = self
+            .worktree
+            .include
+            .iter()
+            .filter(|r| !project.worktree.include.iter().any(|p| p.pattern == r.pattern))
+            .cloned()
+            .collect();
+        rules.extend(project.worktree.include.iter().cloned());
+        rules
+    }
+
+    /// Worktree location template, the project's taking precedence
+    pub fn worktree_root<'a>(&'a self, project: &'a ProjectConfig) -> &'a str {
+        project
+            .worktree
+            .root
+            .as_deref()
+            .or(self.worktree.root.as_deref())
+            .unwrap_or(WorktreeConfig::DEFAULT_ROOT)
+    }
+
+    /// Submodule handling, the project's setting taking precedence
+    pub fn submodule_mode(&self, project: &ProjectConfig) -> SubmoduleMode {
+        project
+            .worktree
+            .submodules
+            .or(self.worktree.submodules)
+            .unwrap_or_default()
+    }
+
+    /// Copy ignore patterns from the global config and the project
+    pub fn ignore_patterns(&self, project: &ProjectConfig) -> Vec<String> {
+        let mut patterns = self.worktree.ignore.clone();
+        for pattern in &project.worktree.ignore {
+            if !patterns.contains(pattern) {
+                patterns.push(pattern.clone());
+            }
+        }
+        patterns
+    }
+
+    /// Global setup commands followed by the project's
+    pub fn setup_commands(&self, project: &ProjectConfig) -> Vec<String> {
+        self.setup.iter().chain(&project.setup).cloned().collect()
+    }
+
+    /// Protected ref globs from the global config and the project, as full
+    /// ref names
+    pub fn protected_refs(&self, project: &ProjectConfig) -> Vec<String> {
+        let mut refs: Vec<String> = Vec::new();
+        for name in self.guard.protected.iter().chain(&project.guard.protected) {
+            let full = if name.starts_with("refs/") {
+                name.clone()
+            } else {
+                format!("refs/heads/{}", name)
+            };
+            if !refs.contains(&full) {
+                refs.push(full);
+            }
+        }
+        if refs.is_empty() {
+            refs = vec!["refs/heads/main".to_string(), "refs/heads/master".to_string()];
+        }
+        refs
+    }
+
+    /// Paths of a named sparse profile, the project's taking precedence
+    pub fn sparse_profile(&self, project: &ProjectConfig, name: &str) -> Option<Vec<String>> {
+        project
+            .sparse
+            .get(name)
+            .or_else(|| self.sparse.get(name))
+            .cloned()
+    }
+
     pub fn get(&self, key: &str) -> Option<&String> {
         self.settings.get(key)
     }
@@ -115,6 +423,16 @@ impl Config {
         self.settings.insert(key, value);
     }
 
+    pub fn keymap(&self) -> Result<Keymap> {
+        Keymap::new(self.tui.prefix(), &self.keys)
+    }
+
+    /// The configured theme, or no colors when NO_COLOR is set and the
+    /// config doesn't pick a theme
+    pub fn theme(&self) -> Result<Theme> {
+        Theme::new(&self.theme, theme::no_color())
+    }
+
     pub fn get_plugin(&self, name: &str) -> Option<&PluginConfig> {
         self.plugins.get(name)
     }
@@ -127,3 +445,27 @@ impl Config {
         self.plugins.remove(name)
     }
 }
+
+impl ProjectConfig {
+    pub const FILE_NAME: &'static str = ".acta.yaml";
+
+    /// Load the project file from `repo`, falling back to defaults when absent
+    pub fn load(repo: &Path) -> Result<Self> {
+        let path = repo.join(Self::FILE_NAME);
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read project config from {}", path.display()))?;
+
+        let project: Self = serde_yaml::from_str(&contents)
+            .with_context(|| format!("Failed to parse project config from {}", path.display()))?;
+        for rule in &project.worktree.include {
+            rule.check()
+                .with_context(|| format!("Invalid worktree.include in {}", path.display()))?;
+        }
+        Ok(project)
+    }
+}
@@ -0,0 +1,386 @@
+// TUI colors: named style slots (`header: bold cyan`, `status-bar: black on
+// cyan`) filled in from a built-in theme, with the user's `theme:` slots
+// applied on top
+
+use anyhow::{anyhow, bail, Context, Result};
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::session::SessionStatus;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThemeConfig {
+    /// Built-in theme the slots start from: `dark`, `light`,
+    /// `high-contrast` or `none` (default `dark`, or `none` when NO_COLOR
+    /// is set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+
+    /// Syntax highlighting theme for code in the diff viewer, or `none`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub syntax: Option<String>,
+
+    /// Slot name to style
+    #[serde(flatten)]
+    pub slots: HashMap<String, String>,
+}
+
+/// Every slot a theme sets, as written in config
+pub const SLOTS: &[&str] = &[
+    "header",
+    "selected",
+    "footer",
+    "muted",
+    "error",
+    "border",
+    "border-inactive",
+    "border-focused",
+    "border-typing",
+    "status-bar",
+    "prompt",
+    "badge",
+    "copy-selection",
+    "copy-match",
+    "diff-added",
+    "diff-removed",
+    "diff-added-line",
+    "diff-removed-line",
+    "diff-hunk",
+    "status-preparing",
+    "status-running",
+    "status-needs-input",
+    "status-conflicted",
+    "status-failed",
+    "status-stopped",
+];
+
+pub const THEMES: &[&str] = &["dark", "light", "high-contrast", "none"];
+
+const DARK: &[(&str, &str)] = &[
+    ("header", "bold cyan"),
+    ("selected", "bold yellow"),
+    ("footer", "gray"),
+    ("muted", "dark-gray"),
+    ("error", "red"),
+    ("border", ""),
+    ("border-inactive", "dark-gray"),
+    ("border-focused", "bold yellow"),
+    ("border-typing", "bold green"),
+    ("status-bar", "black on cyan"),
+    ("prompt", "black on yellow"),
+    ("badge", "bold black on magenta"),
+    ("copy-selection", "black on yellow"),
+    ("copy-match", "black on cyan"),
+    ("diff-added", "green"),
+    ("diff-removed", "red"),
+    ("diff-added-line", "on #1f3b24"),
+    ("diff-removed-line", "on #4b1f23"),
+    ("diff-hunk", "cyan"),
+    ("status-preparing", "blue"),
+    ("status-running", "green"),
+    ("status-needs-input", "bold magenta"),
+    ("status-conflicted", "bold red"),
+    ("status-failed", "red"),
+    ("status-stopped", "dark-gray"),
+];
+
+const LIGHT: &[(&str, &str)] = &[
+    ("header", "bold blue"),
+    ("selected", "bold black on #d0d7e5"),
+    ("footer", "dark-gray"),
+    ("muted", "244"),
+    ("error", "red"),
+    ("border", ""),
+    ("border-inactive", "244"),
+    ("border-focused", "bold blue"),
+    ("border-typing", "bold green"),
+    ("status-bar", "white on blue"),
+    ("prompt", "black on yellow"),
+    ("badge", "bold white on magenta"),
+    ("copy-selection", "black on yellow"),
+    ("copy-match", "black on cyan"),
+    ("diff-added", "green"),
+    ("diff-removed", "red"),
+    ("diff-added-line", "on #e6ffec"),
+    ("diff-removed-line", "on #ffebe9"),
+    ("diff-hunk", "blue"),
+    ("status-preparing", "blue"),
+    ("status-running", "green"),
+    ("status-needs-input", "bold magenta"),
+    ("status-conflicted", "bold red"),
+    ("status-failed", "red"),
+    ("status-stopped", "244"),
+];
+
+const HIGH_CONTRAST: &[(&str, &str)] = &[
+    ("header", "bold white"),
+    ("selected", "bold black on light-yellow"),
+    ("footer", "white"),
+    ("muted", "gray"),
+    ("error", "bold light-red"),
+    ("border", "white"),
+    ("border-inactive", "white"),
+    ("border-focused", "bold light-yellow"),
+    ("border-typing", "bold light-green"),
+    ("status-bar", "bold black on white"),
+    ("prompt", "bold black on light-yellow"),
+    ("badge", "bold black on light-magenta"),
+    ("copy-selection", "black on light-yellow"),
+    ("copy-match", "black on light-cyan"),
+    ("diff-added", "bold light-green"),
+    ("diff-removed", "bold light-red"),
+    ("diff-added-line", ""),
+    ("diff-removed-line", ""),
+    ("diff-hunk", "bold light-cyan"),
+    ("status-preparing", "light-blue"),
+    ("status-running", "light-green"),
+    ("status-needs-input", "bold light-magenta"),
+    ("status-conflicted", "bold light-red"),
+    ("status-failed", "bold light-red"),
+    ("status-stopped", "white"),
+];
+
+/// Attributes only, for NO_COLOR
+const NONE: &[(&str, &str)] = &[
+    ("header", "bold"),
+    ("selected", "bold reversed"),
+    ("error", "bold"),
+    ("border-focused", "bold"),
+    ("border-typing", "bold"),
+    ("status-bar", "reversed"),
+    ("prompt", "bold reversed"),
+    ("badge", "bold reversed"),
+    ("copy-selection", "reversed"),
+    ("copy-match", "underlined"),
+    ("diff-hunk", "bold"),
+    ("status-needs-input", "bold"),
+    ("status-conflicted", "bold"),
+    ("status-failed", "bold"),
+];
+
+/// Styles for every part of the TUI
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub header: Style,
+    /// Selected list rows, diff files and dialog fields
+    pub selected: Style,
+    /// Help and messages under the panes
+    pub footer: Style,
+    /// Hints, placeholders and notices
+    pub muted: Style,
+    pub error: Style,
+    pub border: Style,
+    /// Panes other than the focused one
+    pub border_inactive: Style,
+    /// The focused pane and dialogs
+    pub border_focused: Style,
+    /// The focused pane while typing into it
+    pub border_typing: Style,
+    /// Status line of the attached view
+    pub status_bar: Style,
+    /// Status line while a key sequence or a prompt is being typed
+    pub prompt: Style,
+    /// Count of sessions waiting for input
+    pub badge: Style,
+    pub copy_selection: Style,
+    pub copy_match: Style,
+    /// Diff markers and line counts
+    pub diff_added: Style,
+    pub diff_removed: Style,
+    /// Whole added and removed diff lines
+    pub diff_added_line: Style,
+    pub diff_removed_line: Style,
+    pub diff_hunk: Style,
+    /// Syntax highlighting theme for the diff viewer
+    pub syntax: Option<String>,
+    statuses: [Style; 6],
+}
+
+impl Theme {
+    /// `no_color` picks the `none` theme when the config doesn't name one
+    pub fn new(config: &ThemeConfig, no_color: bool) -> Result<Self> {
+        let base = match config.base.as_deref() {
+            Some(base) => base,
+            None if no_color => "none",
+            None => "dark",
+        };
+        let (builtin, syntax) = match base {
+            "dark" => (DARK, Some("base16-ocean.dark")),
+            "light" => (LIGHT, Some("InspiredGitHub")),
+            "high-contrast" => (HIGH_CONTRAST, None),
+            "none" => (NONE, None),
+            _ => bail!("Unknown theme '{}' (expected one of: {})", base, THEMES.join(", ")),
+        };
+
+        let mut specs: HashMap<&str, &str> = builtin.iter().copied().collect();
+        let mut slots: Vec<_> = config.slots.iter().collect();
+        slots.sort();
+        for (slot, spec) in slots {
+            let Some(slot) = SLOTS.iter().find(|s| *s == slot) else {
+                bail!("Unknown theme slot '{}' (expected one of: {})", slot, SLOTS.join(", "));
+            };
+            specs.insert(slot, spec);
+        }
+        let style = |slot: &str| -> Result<Style> {
+            let spec = specs.get(slot).copied().unwrap_or_default();
+            parse_style(spec).with_context(|| format!("Invalid theme.{} '{}'", slot, spec))
+        };
+
+        Ok(Self {
+            header: style("header")?,
+            selected: style("selected")?,
+            footer: style("footer")?,
+            muted: style("muted")?,
+            error: style("error")?,
+            border: style("border")?,
+            border_inactive: style("border-inactive")?,
+            border_focused: style("border-focused")?,
+            border_typing: style("border-typing")?,
+            status_bar: style("status-bar")?,
+            prompt: style("prompt")?,
+            badge: style("badge")?,
+            copy_selection: style("copy-selection")?,
+            copy_match: style("copy-match")?,
+            diff_added: style("diff-added")?,
+            diff_removed: style("diff-removed")?,
+            diff_added_line: style("diff-added-line")?,
+            diff_removed_line: style("diff-removed-line")?,
+            diff_hunk: style("diff-hunk")?,
+            syntax: match config.syntax.as_deref() {
+                Some("none") => None,
+                Some(name) => Some(name.to_string()),
+                None => syntax.map(str::to_string),
+            },
+            statuses: [
+                style("status-preparing")?,
+                style("status-running")?,
+                style("status-needs-input")?,
+                style("status-conflicted")?,
+                style("status-failed")?,
+                style("status-stopped")?,
+            ],
+        })
+    }
+
+    pub fn status(&self, status: &SessionStatus) -> Style {
+        self.statuses[match status {
+            SessionStatus::Preparing => 0,
+            SessionStatus::Running => 1,
+            SessionStatus::NeedsInput => 2,
+            SessionStatus::Conflicted => 3,
+            SessionStatus::Failed => 4,
+            SessionStatus::Stopped => 5,
+        }]
+    }
+}
+
+/// Whether the NO_COLOR convention (no-color.org) asks for no colors
+pub fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+/// A style written as attributes and colors: `bold yellow`,
+/// `black on cyan`, `on #1f3b24`. Colors are names, `#rrggbb` or 0-255.
+fn parse_style(spec: &str) -> Result<Style> {
+    let mut style = Style::default();
+    let mut background = false;
+    for word in spec.split_whitespace() {
+        let modifier = match word {
+            "on" => {
+                background = true;
+                continue;
+            }
+            "bold" => Modifier::BOLD,
+            "dim" => Modifier::DIM,
+            "italic" => Modifier::ITALIC,
+            "underlined" => Modifier::UNDERLINED,
+            "reversed" => Modifier::REVERSED,
+            color => {
+                let color: Color = color.parse().map_err(|_| anyhow!("Unknown color '{}'", color))?;
+                style = if background { style.bg(color) } else { style.fg(color) };
+                continue;
+            }
+        };
+        style = style.add_modifier(modifier);
+    }
+    Ok(style)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn styles_combine_attributes_and_colors() {
+        assert_eq!(parse_style("").unwrap(), Style::default());
+        assert_eq!(parse_style("bold yellow").unwrap(), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+        assert_eq!(parse_style("black on cyan").unwrap(), Style::default().fg(Color::Black).bg(Color::Cyan));
+        assert_eq!(parse_style("on #1f3b24").unwrap(), Style::default().bg(Color::Rgb(0x1f, 0x3b, 0x24)));
+        assert_eq!(
+            parse_style("dim italic 244 on light-magenta").unwrap(),
+            Style::default()
+                .fg(Color::Indexed(244))
+                .bg(Color::LightMagenta)
+                .add_modifier(Modifier::DIM | Modifier::ITALIC)
+        );
+        assert_eq!(parse_style("underlined reversed").unwrap().add_modifier, Modifier::UNDERLINED | Modifier::REVERSED);
+    }
+
+    #[test]
+    fn unknown_colors_are_errors() {
+        let e = parse_style("bold blurple").unwrap_err();
+        assert_eq!(e.to_string(), "Unknown color 'blurple'");
+        assert!(parse_style("on").is_ok());
+    }
+
+    #[test]
+    fn builtin_themes_set_every_slot() {
+        for builtin in [DARK, LIGHT, HIGH_CONTRAST] {
+            let slots: Vec<&str> = builtin.iter().map(|(slot, _)| *slot).collect();
+            assert_eq!(slots, SLOTS);
+        }
+        assert!(NONE.iter().all(|(slot, _)| SLOTS.contains(slot)));
+        for base in THEMES {
+            let config = ThemeConfig {
+                base: Some(base.to_string()),
+                ..Default::default()
+            };
+            Theme::new(&config, false).unwrap();
+        }
+    }
+
+    #[test]
+    fn user_slots_override_the_base() {
+        let mut config = ThemeConfig::default();
+        config.slots.insert("header".to_string(), "italic".to_string());
+        config.syntax = Some("none".to_string());
+        let theme = Theme::new(&config, true).unwrap();
+        assert_eq!(theme.header, Style::default().add_modifier(Modifier::ITALIC));
+        assert_eq!(theme.status_bar, Style::default().add_modifier(Modifier::REVERSED));
+        assert_eq!(theme.syntax, None);
+
+        let theme = Theme::new(&ThemeConfig::default(), false).unwrap();
+        assert_eq!(theme.status(&SessionStatus::Running), Style::default().fg(Color::Green));
+        assert_eq!(theme.syntax.as_deref(), Some("base16-ocean.dark"));
+    }
+
+    #[test]
+    fn bad_config_names_the_problem() {
+        let config = ThemeConfig {
+            base: Some("solarized".to_string()),
+            ..Default::default()
+        };
+        let e = Theme::new(&config, false).unwrap_err();
+        assert!(e.to_string().starts_with("Unknown theme 'solarized'"));
+
+        let mut config = ThemeConfig::default();
+        config.slots.insert("headr".to_string(), "bold".to_string());
+        let e = Theme::new(&config, false).unwrap_err();
+        assert!(e.to_string().starts_with("Unknown theme slot 'headr'"));
+
+        let mut config = ThemeConfig::default();
+        config.slots.insert("footer".to_string(), "on purple-ish".to_string());
+        let e = Theme::new(&config, false).unwrap_err();
+        assert_eq!(format!("{:#}", e), "Invalid theme.footer 'on purple-ish': Unknown color 'purple-ish'");
+    }
+}
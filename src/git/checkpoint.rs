@@ -0,0 +1,392 @@
+// Checkpoint snapshots of session worktrees
+// Stored as commits under refs/acta/<id>/checkpoints/<n>, never on the session branch
+
+use anyhow::{Context, Result};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use uuid::Uuid;
+use super::git_path;
+use crate::config::CheckpointConfig;
+
+/// What caused a checkpoint to be taken
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trigger {
+    Manual,
+    Idle,
+    Interval,
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Trigger::Manual => "manual",
+            Trigger::Idle => "idle",
+            Trigger::Interval => "interval",
+        };
+        f.write_str(s)
+    }
+}
+
+impl Trigger {
+    fn parse(s: &str) -> Self {
+        match s {
+            "idle" => Trigger::Idle,
+            "interval" => Trigger::Interval,
+            _ => Trigger::Manual,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    pub number: u32,
+    pub commit: String,
+    pub parent: String,
+    pub tree: String,
+    pub trigger: Trigger,
+    pub created_at: String,
+    pub age: String,
+}
+
+/// Ref namespace holding all checkpoints of a session
+pub fn ref_prefix(session_id: &str) -> String {
+    format!("refs/acta/{}/checkpoints/", session_id)
+}
+
+/// Write the current worktree contents (including untracked files) to a tree
+/// object without touching the real index
+pub fn snapshot_tree(worktree: &Path) -> Result<String> {
+    let index = git_path(worktree, "index")?;
+    // Checkpoints, rollbacks and overlap checks can snapshot the same
+    // worktree at once, so each gets its own index
+    let tmp_index = git_path(worktree, &format!("acta-index-{}", Uuid::new_v4()))?;
+
+    let result = write_tree(worktree, &index, &tmp_index);
+    let _ = fs::remove_file(&tmp_index);
+    result
+}
+
+fn write_tree(worktree: &Path, index: &Path, tmp_index: &Path) -> Result<String> {
+    // Seed from the real index so unchanged files hit the stat cache. The copy
+    // keeps the original mtime, otherwise git's racy-clean check would trust
+    // entries written in the same second as the index.
+    if index.exists() {
+        fs::copy(index, tmp_index).context("Failed to copy index")?;
+        let modified = fs::metadata(index)?.modified()?;
+        fs::File::options()
+            .write(true)
+            .open(tmp_index)?
+            .set_modified(modified)?;
+    }
+
+    // In sparse worktrees, pick up files written outside the cone too;
+    // entries skipped by the sparse checkout are left as they are
+    let add: &[&str] = if super::is_sparse(worktree) {
+        &["add", "-A", "--sparse"]
+    } else {
+        &["add", "-A"]
+    };
+
+    let env = [("GIT_INDEX_FILE", tmp_index.as_os_str())];
+    super::run_with_env(worktree, add, &env)?;
+    super::run_with_env(worktree, &["write-tree"], &env)
+}
+
+/// Take a checkpoint of the worktree. Returns `None` when nothing changed
+/// since the latest checkpoint.
+pub fn create(
+    worktree: &Path,
+    session_id: &str,
+    trigger: Trigger,
+    message: Option<&str>,
+) -> Result<Option<Checkpoint>> {
+    let tree = snapshot_tree(worktree)?;
+    let parent = super::rev_parse(worktree, "HEAD")?;
+
+    // The TUI, `--watch` and manual checkpoints can race for the next
+    // number; refs are only ever created, so the loser takes the one after
+    loop {
+        let latest = list(worktree, session_id)?.pop();
+        if latest.as_ref().map(|c| c.tree == tree).unwrap_or(false) {
+            return Ok(None);
+        }
+
+        let number = latest.map(|c| c.number + 1).unwrap_or(1);
+        let subject = format!("acta checkpoint {} ({})", number, trigger);
+        let mut args = vec!["commit-tree", tree.as_str(), "-p", parent.as_str(), "-m", subject.as_str()];
+        if let Some(message) = message {
+            args.extend(["-m", message]);
+        }
+        let commit = super::run(worktree, &args)?;
+
+        let refname = format!("{}{}", ref_prefix(session_id), number);
+        match super::run(worktree, &["update-ref", &refname, &commit, ""]) {
+            Ok(_) => {}
+            Err(_) if super::rev_parse(worktree, &refname).is_ok() => continue,
+            Err(e) => return Err(e),
+        }
+
+        return Ok(list(worktree, session_id)?
+            .into_iter()
+            .find(|c| c.number == number));
+    }
+}
+
+/// List checkpoints of a session, oldest first
+pub fn list(worktree: &Path, session_id: &str) -> Result<Vec<Checkpoint>> {
+    let output = super::run(
+        worktree,
+        &[
+            "for-each-ref",
+            "--format=%(refname:lstrip=-1)|%(objectname)|%(parent)|%(tree)|%(creatordate:iso)|%(creatordate:relative)|%(subject)",
+            &ref_prefix(session_id),
+        ],
+    )?;
+
+    let mut checkpoints: Vec<Checkpoint> = output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.splitn(7, '|').collect();
+            if fields.len() != 7 {
+                return None;
+            }
+            let trigger = fields[6]
+                .rsplit_once('(')
+                .map(|(_, t)| t.trim_end_matches(')'))
+                .unwrap_or("manual");
+            Some(Checkpoint {
+                number: fields[0].parse().ok()?,
+                commit: fields[1].to_string(),
+                parent: fields[2].to_string(),
+                tree: fields[3].to_string(),
+                trigger: Trigger::parse(trigger),
+                created_at: fields[4].to_string(),
+                age: fields[5].to_string(),
+            })
+        })
+        .collect();
+
+    checkpoints.sort_by_key(|c| c.number);
+    Ok(checkpoints)
+}
+
+/// Summarise what a checkpoint changed since `previous`, or since the commit
+/// it was taken on for the first checkpoint
+pub fn diffstat(worktree: &Path, checkpoint: &Checkpoint, previous: Option<&Checkpoint>) -> Result<String> {
+    let from = previous.map(|p| &p.commit).unwrap_or(&checkpoint.parent);
+    let stat = super::run(worktree, &["diff", "--shortstat", from, &checkpoint.commit])?;
+    Ok(if stat.is_empty() {
+        "no changes".to_string()
+    } else {
+        stat.trim().to_string()
+    })
+}
+
+/// Restore the worktree files to the state captured in checkpoint `number`.
+/// HEAD and the session branch stay where they are, so commits made since
+/// the checkpoint are kept and the restored files show up as changes
+/// against them. The current state is checkpointed first so the rollback
+/// itself can be undone.
+pub fn rollback(worktree: &Path, session_id: &str, number: u32) -> Result<Option<Checkpoint>> {
+    let target = list(worktree, session_id)?
+        .into_iter()
+        .find(|c| c.number == number)
+        .with_context(|| format!("Checkpoint {} not found", number))?;
+
+    let safety = create(
+        worktree,
+        session_id,
+        Trigger::Manual,
+        Some(&format!("Automatic checkpoint before rollback to {}", number)),
+    )?;
+
+    // Bring index and tracked files in line with the checkpoint and drop
+    // files created since, then point the index back at HEAD so nothing is
+    // left staged
+    super::run(worktree, &["read-tree", "--reset", "-u", &target.tree])?;
+    super::run(worktree, &["clean", "-fdq"])?;
+    super::run(worktree, &["reset", "-q"])?;
+
+    Ok(safety)
+}
+
+/// When automatic checkpoints are due for a session whose agent runs in
+/// this process, going by when the agent last wrote to its terminal
+pub struct Schedule {
+    idle: Option<Duration>,
+    interval: Option<Duration>,
+    /// Latest agent activity seen
+    active: Option<SystemTime>,
+    /// Whether the quiet spell since `active` has been checkpointed
+    idle_taken: bool,
+    last_interval: SystemTime,
+}
+
+impl Schedule {
+    /// None when automatic checkpoints are turned off
+    pub fn new(config: &CheckpointConfig, now: SystemTime) -> Option<Self> {
+        let idle = config.on_idle.then(|| Duration::from_secs(config.idle_secs));
+        let interval = config.interval_secs.map(Duration::from_secs);
+        (idle.is_some() || interval.is_some()).then_some(Self {
+            idle,
+            interval,
+            active: None,
+            idle_taken: true,
+            last_interval: now,
+        })
+    }
+
+    /// Checkpoint to take at `now`, given the agent's latest activity. Idle
+    /// checkpoints follow each burst of activity once it has died down;
+    /// interval ones are only taken while the agent keeps working.
+    pub fn due(&mut self, activity: Option<SystemTime>, now: SystemTime) -> Option<Trigger> {
+        if activity > self.active {
+            self.active = activity;
+            self.idle_taken = false;
+        }
+        let since = |t: SystemTime| now.duration_since(t).unwrap_or_default();
+
+        if let (Some(idle), Some(active)) = (self.idle, self.active) {
+            if !self.idle_taken && since(active) >= idle {
+                self.idle_taken = true;
+                return Some(Trigger::Idle);
+            }
+        }
+        if let Some(interval) = self.interval {
+            if since(self.last_interval) >= interval {
+                let working = self.active.is_some_and(|a| a > self.last_interval);
+                self.last_interval = now;
+                if working {
+                    return Some(Trigger::Interval);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::testing::Repo;
+
+    #[test]
+    fn rollback_restores_files_without_moving_head() {
+        let repo = Repo::new();
+        repo.write("a.txt", "two\n");
+        let first = create(&repo.dir, "s", Trigger::Manual, None).unwrap().unwrap();
+
+        repo.commit("agent work");
+        let head = repo.git(&["rev-parse", "HEAD"]);
+        repo.write("a.txt", "three\n");
+        repo.write("new.txt", "new\n");
+
+        let safety = rollback(&repo.dir, "s", first.number).unwrap().unwrap();
+
+        assert_eq!(repo.git(&["rev-parse", "HEAD"]), head);
+        assert_eq!(repo.read("a.txt").as_deref(), Some("two\n"));
+        assert_eq!(repo.read("new.txt"), None);
+        assert_eq!(repo.git(&["diff", "--cached", "--name-only"]), "");
+        assert_eq!(safety.number, 2);
+
+        rollback(&repo.dir, "s", safety.number).unwrap();
+        assert_eq!(repo.read("a.txt").as_deref(), Some("three\n"));
+        assert_eq!(repo.read("new.txt").as_deref(), Some("new\n"));
+    }
+
+    #[test]
+    fn concurrent_snapshots_agree_and_clean_up() {
+        let repo = Repo::new();
+        for i in 0..20 {
+            repo.write(&format!("file{}.txt", i), &"x".repeat(i * 100));
+        }
+        let expected = snapshot_tree(&repo.dir).unwrap();
+
+        let trees: Vec<String> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8).map(|_| scope.spawn(|| snapshot_tree(&repo.dir).unwrap())).collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        assert!(trees.iter().all(|t| *t == expected));
+        let leftovers = fs::read_dir(repo.dir.join(".git"))
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().starts_with("acta-"))
+            .count();
+        assert_eq!(leftovers, 0);
+        assert_eq!(repo.git(&["status", "--porcelain"]).lines().count(), 20);
+    }
+
+    #[test]
+    fn racing_checkpoints_never_share_a_number() {
+        let repo = Repo::new();
+        let taken: Vec<Checkpoint> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|i| {
+                    let repo = &repo;
+                    scope.spawn(move || {
+                        repo.write(&format!("file{}.txt", i), "x\n");
+                        create(&repo.dir, "s", Trigger::Manual, None).unwrap()
+                    })
+                })
+                .collect();
+            handles.into_iter().filter_map(|h| h.join().unwrap()).collect()
+        });
+
+        let mut numbers: Vec<u32> = taken.iter().map(|c| c.number).collect();
+        numbers.sort();
+        assert_eq!(numbers, (1..=taken.len() as u32).collect::<Vec<_>>());
+        let stored = list(&repo.dir, "s").unwrap();
+        assert_eq!(stored.len(), taken.len());
+        for checkpoint in &taken {
+            assert!(stored.iter().any(|c| c.number == checkpoint.number && c.commit == checkpoint.commit));
+        }
+    }
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn schedule_takes_one_idle_checkpoint_per_quiet_spell() {
+        let config = CheckpointConfig {
+            on_idle: true,
+            idle_secs: 30,
+            interval_secs: None,
+        };
+        let mut schedule = Schedule::new(&config, at(0)).unwrap();
+
+        assert_eq!(schedule.due(None, at(100)), None);
+        assert_eq!(schedule.due(Some(at(110)), at(120)), None);
+        assert_eq!(schedule.due(Some(at(110)), at(140)), Some(Trigger::Idle));
+        assert_eq!(schedule.due(Some(at(110)), at(200)), None);
+        assert_eq!(schedule.due(Some(at(210)), at(239)), None);
+        assert_eq!(schedule.due(Some(at(210)), at(240)), Some(Trigger::Idle));
+    }
+
+    #[test]
+    fn schedule_takes_interval_checkpoints_while_working() {
+        let config = CheckpointConfig {
+            on_idle: false,
+            idle_secs: 30,
+            interval_secs: Some(60),
+        };
+        let mut schedule = Schedule::new(&config, at(0)).unwrap();
+
+        assert_eq!(schedule.due(Some(at(10)), at(30)), None);
+        assert_eq!(schedule.due(Some(at(50)), at(60)), Some(Trigger::Interval));
+        assert_eq!(schedule.due(Some(at(50)), at(120)), None);
+        assert_eq!(schedule.due(Some(at(170)), at(180)), Some(Trigger::Interval));
+    }
+
+    #[test]
+    fn schedule_is_off_without_triggers() {
+        let config = CheckpointConfig {
+            on_idle: false,
+            idle_secs: 30,
+            interval_secs: None,
+        };
+        assert!(Schedule::new(&config, at(0)).is_none());
+    }
+}
@@ -0,0 +1,231 @@
+// Copy-based isolation for project directories that are not git repositories
+//
+// The directory is copied into the session workspace and a private git
+// directory (outside the workspace, linked through a `.git` file) records
+// the copied state as the base commit. Diffs, checkpoints and apply-back
+// then work the same way as for worktree sessions.
+
+use anyhow::{bail, Context, Result};
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use super::diff::{self, FileStat};
+use super::isolation::{
+    self, BackendKind, Checkout, Diff, Isolation, MergeOutcome, Workspace, WorkspaceSpec,
+    WorkspaceStatus,
+};
+use crate::session::Session;
+
+/// Always skipped when copying
+const ALWAYS_IGNORED: &[&str] = &[".acta", ".git"];
+
+/// Identity for the base snapshot commit, independent of user config
+const SNAPSHOT_ENV: [(&str, &str); 4] = [
+    ("GIT_AUTHOR_NAME", "acta"),
+    ("GIT_AUTHOR_EMAIL", "acta@localhost"),
+    ("GIT_COMMITTER_NAME", "acta"),
+    ("GIT_COMMITTER_EMAIL", "acta@localhost"),
+];
+
+/// Isolation by copying the project; private git dirs live in `state_dir`
+pub struct DirectoryCopy {
+    state_dir: PathBuf,
+}
+
+impl DirectoryCopy {
+    pub fn new(state_dir: &Path) -> Self {
+        Self {
+            state_dir: state_dir.to_path_buf(),
+        }
+    }
+
+    /// Private git directory tracking a copy-isolated session
+    fn git_dir(&self, id: &str) -> PathBuf {
+        self.state_dir.join(format!("{}.git", id))
+    }
+}
+
+impl Isolation for DirectoryCopy {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Copy
+    }
+
+    fn create(&self, spec: &WorkspaceSpec) -> Result<Workspace> {
+        if !matches!(spec.checkout, Checkout::NewBranch) {
+            bail!("--branch, --detach and --base need a git repository");
+        }
+        if !spec.sparse.is_empty() {
+            bail!("--sparse needs a git repository");
+        }
+
+        let branch = format!("acta/{}", &spec.id[..8]);
+        let git_dir = self.git_dir(spec.id);
+        match create(spec.source, spec.path, spec.nested, &git_dir, &branch, spec.ignore) {
+            Ok(base) => Ok(Workspace {
+                branch: Some(branch),
+                base_commit: Some(base),
+                owns_branch: false,
+            }),
+            Err(e) => {
+                let _ = remove(spec.path, &git_dir);
+                Err(e)
+            }
+        }
+    }
+
+    fn remove(&self, session: &Session) -> Result<()> {
+        let workspace = std::path::absolute(&session.worktree_path)?;
+        remove(&workspace, &self.git_dir(&session.id))
+    }
+
+    fn diff(&self, session: &Session, color: bool) -> Result<Diff> {
+        isolation::git_diff(session, color)
+    }
+
+    fn status(&self, session: &Session) -> Result<WorkspaceStatus> {
+        isolation::git_status(session)
+    }
+
+    fn merge(&self, session: &Session) -> Result<MergeOutcome> {
+        let source = session
+            .source_path
+            .as_deref()
+            .context("Session has no source directory")?;
+        let base = session
+            .base_commit
+            .as_deref()
+            .context("Session has no base commit")?;
+        let workspace = std::path::absolute(&session.worktree_path)?;
+        let patch = self.state_dir.join(format!("{}.patch", session.id));
+
+        let stats = apply_back(&workspace, source, base, &patch)?;
+        if stats.is_empty() {
+            return Ok(MergeOutcome::NothingToMerge);
+        }
+        Ok(MergeOutcome::Merged {
+            files: stats.len(),
+            into: source.display().to_string(),
+        })
+    }
+}
+
+/// Copy `source` into `workspace`, skipping `ignore` globs and any session
+/// workspaces nested in `source`, and record the copy as the base commit on
+/// `branch`. Returns the base commit.
+fn create(
+    source: &Path,
+    workspace: &Path,
+    nested: Option<&Path>,
+    git_dir: &Path,
+    branch: &str,
+    ignore: &[String],
+) -> Result<String> {
+    let patterns = ignore
+        .iter()
+        .map(|p| glob::Pattern::new(p).with_context(|| format!("Invalid ignore pattern '{}'", p)))
+        .collect::<Result<Vec<_>>>()?;
+
+    fs::create_dir_all(workspace).context("Failed to create session workspace")?;
+    copy_tree(source, workspace, Path::new(""), &patterns, nested.unwrap_or(workspace))?;
+
+    let parent = workspace.parent().unwrap_or(workspace);
+    super::run(
+        parent,
+        &[
+            "init",
+            "--quiet",
+            "--initial-branch",
+            branch,
+            "--separate-git-dir",
+            &git_dir.to_string_lossy(),
+            &workspace.to_string_lossy(),
+        ],
+    )?;
+
+    let exclude = git_dir.join("info").join("exclude");
+    fs::create_dir_all(exclude.parent().unwrap())?;
+    fs::write(&exclude, ignore.join("\n") + "\n").context("Failed to write exclude file")?;
+
+    let env: Vec<(&str, &OsStr)> = SNAPSHOT_ENV.iter().map(|(k, v)| (*k, OsStr::new(v))).collect();
+    super::run(workspace, &["add", "-A"])?;
+    super::run_with_env(
+        workspace,
+        &["commit", "--quiet", "--allow-empty", "--no-verify", "-m", "acta base snapshot"],
+        &env,
+    )?;
+
+    super::rev_parse(workspace, "HEAD")
+}
+
+/// Delete the workspace copy and its private git directory
+fn remove(workspace: &Path, git_dir: &Path) -> Result<()> {
+    if workspace.exists() {
+        fs::remove_dir_all(workspace)
+            .with_context(|| format!("Failed to remove {}", workspace.display()))?;
+    }
+    if git_dir.exists() {
+        fs::remove_dir_all(git_dir)
+            .with_context(|| format!("Failed to remove {}", git_dir.display()))?;
+    }
+    Ok(())
+}
+
+/// Apply everything changed in the workspace since `base` back onto
+/// `source`. The patch is applied atomically: if any hunk no longer fits
+/// because the source moved on, nothing is written.
+fn apply_back(workspace: &Path, source: &Path, base: &str, patch_file: &Path) -> Result<Vec<FileStat>> {
+    let tree = super::checkpoint::snapshot_tree(workspace)?;
+    let stats = diff::numstat(workspace, base, &tree)?;
+    if stats.is_empty() {
+        return Ok(stats);
+    }
+
+    let patch = super::run(workspace, &["diff", "--binary", "--no-color", base, &tree])?;
+    fs::write(patch_file, patch + "\n").context("Failed to write patch")?;
+
+    let result = super::run(source, &["apply", "--binary", &patch_file.to_string_lossy()]);
+    let _ = fs::remove_file(patch_file);
+    if let Err(e) = result {
+        bail!("Changes no longer apply cleanly to {}: {:#}", source.display(), e);
+    }
+
+    Ok(stats)
+}
+
+/// `skip` holds session workspaces and may live inside the source
+fn copy_tree(
+    src: &Path,
+    dst: &Path,
+    relative: &Path,
+    ignore: &[glob::Pattern],
+    skip: &Path,
+) -> Result<()> {
+    for entry in fs::read_dir(src).with_context(|| format!("Failed to read {}", src.display()))? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let rel = relative.join(&name);
+
+        if ALWAYS_IGNORED.iter().any(|d| name == *d)
+            || entry.path() == skip
+            || ignore.iter().any(|p| p.matches_path(&rel) || p.matches(&name.to_string_lossy()))
+        {
+            continue;
+        }
+
+        let target = dst.join(&name);
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            fs::create_dir_all(&target)?;
+            copy_tree(&entry.path(), &target, &rel, ignore, skip)?;
+        } else if file_type.is_symlink() {
+            super::include::copy_path(&entry.path(), &target, crate::config::IncludeMode::Copy)?;
+        } else {
+            // std::fs::copy lets the kernel clone extents (copy_file_range /
+            // fclonefileat) where the filesystem supports reflinks
+            fs::copy(entry.path(), &target)
+                .with_context(|| format!("Failed to copy {}", rel.display()))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,153 @@
+// Diff helpers shared by compare, conflict detection and the TUI
+
+use anyhow::Result;
+use std::ops::Range;
+use std::path::Path;
+
+/// Lines added/removed in a single file
+#[derive(Debug, Clone)]
+pub struct FileStat {
+    pub path: String,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// Per-file line counts between two revisions or trees
+pub fn numstat(dir: &Path, from: &str, to: &str) -> Result<Vec<FileStat>> {
+    // NUL-terminated records keep paths with tabs, quotes or non-ASCII
+    // characters as they are
+    let output = super::run(dir, &["diff", "--numstat", "-z", "--no-renames", from, to])?;
+
+    Ok(output
+        .split('\0')
+        .filter_map(|record| {
+            let mut fields = record.splitn(3, '\t');
+            let added = fields.next()?;
+            let removed = fields.next()?;
+            let path = fields.next()?;
+            // Binary files report "-" for both counts
+            Some(FileStat {
+                path: path.to_string(),
+                added: added.parse().unwrap_or(0),
+                removed: removed.parse().unwrap_or(0),
+            })
+        })
+        .collect())
+}
+
+/// Paths that differ between two revisions or trees, in diff order
+pub fn names(dir: &Path, from: &str, to: &str) -> Result<Vec<String>> {
+    let output = super::run(dir, &["diff", "--name-only", "-z", "--no-renames", from, to])?;
+    Ok(output
+        .split('\0')
+        .filter(|p| !p.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Unified diff between two revisions or trees
+pub fn patch(dir: &Path, from: &str, to: &str, color: bool) -> Result<String> {
+    let color = if color { "--color=always" } else { "--color=never" };
+    super::run(dir, &["diff", color, "--submodule=log", "--stat", "--patch", from, to])
+}
+
+/// Diffstat summary between two revisions or trees
+pub fn stat(dir: &Path, from: &str, to: &str, color: bool) -> Result<String> {
+    let color = if color { "--color=always" } else { "--color=never" };
+    super::run(dir, &["diff", color, "--stat", from, to])
+}
+
+/// Changed line ranges per file, in `from` coordinates. Pure insertions are
+/// widened to one line so they still collide with edits at the same spot.
+/// Binary files come back with no ranges.
+pub fn hunks(dir: &Path, from: &str, to: &str) -> Result<Vec<(String, Vec<Range<usize>>)>> {
+    let output = super::run(dir, &["diff", "-U0", "--no-renames", "--no-color", from, to])?;
+
+    // `diff --git` headers quote unusual paths and cannot be split reliably
+    // when a path contains " b/", so take the paths from a separate listing.
+    // Both are in the same order; a type change repeats its header for the
+    // deletion and the addition.
+    let mut names = names(dir, from, to)?.into_iter();
+    let mut files: Vec<(String, Vec<Range<usize>>)> = Vec::new();
+    let mut previous = None;
+
+    for line in output.lines() {
+        if let Some(header) = line.strip_prefix("diff --git ") {
+            if previous != Some(header) {
+                previous = Some(header);
+                if let Some(path) = names.next() {
+                    files.push((path, Vec::new()));
+                }
+            }
+        } else if let Some(header) = line.strip_prefix("@@ -") {
+            let old = header.split(' ').next().unwrap_or("");
+            let mut parts = old.splitn(2, ',');
+            let start: usize = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+            let count: usize = parts.next().and_then(|s| s.parse().ok()).unwrap_or(1);
+            if let Some((_, ranges)) = files.last_mut() {
+                ranges.push(start..start + count.max(1));
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::checkpoint;
+    use crate::git::testing::Repo;
+
+    fn lines(range: Range<usize>) -> String {
+        range.map(|i| format!("line {}\n", i)).collect()
+    }
+
+    #[test]
+    fn hunks_are_ranges_in_base_coordinates() {
+        let repo = Repo::new();
+        repo.write("a.txt", &lines(1..21));
+        repo.commit("twenty lines");
+        let base = repo.git(&["rev-parse", "HEAD"]);
+
+        let edited = lines(1..21)
+            .replace("line 3\n", "changed\n")
+            .replace("line 10\nline 11\n", "")
+            .replace("line 15\n", "line 15\ninserted\n");
+        repo.write("a.txt", &edited);
+        repo.write("new.txt", "new\n");
+        let tree = checkpoint::snapshot_tree(&repo.dir).unwrap();
+
+        let files = hunks(&repo.dir, &base, &tree).unwrap();
+        assert_eq!(
+            files,
+            [
+                ("a.txt".to_string(), vec![3..4, 10..12, 15..16]),
+                ("new.txt".to_string(), vec![Range { start: 0, end: 1 }]),
+            ]
+        );
+    }
+
+    #[test]
+    fn hunks_keep_awkward_paths_whole() {
+        let repo = Repo::new();
+        repo.write("dir b/x.txt", "one\n");
+        repo.write("quote\"d.txt", "one\n");
+        repo.write("tab\there.txt", "one\n");
+        repo.commit("awkward paths");
+        let base = repo.git(&["rev-parse", "HEAD"]);
+
+        repo.write("dir b/x.txt", "two\n");
+        repo.write("quote\"d.txt", "two\n");
+        repo.write("tab\there.txt", "two\n");
+        std::fs::remove_file(repo.dir.join("a.txt")).unwrap();
+        std::os::unix::fs::symlink("target", repo.dir.join("a.txt")).unwrap();
+        let tree = checkpoint::snapshot_tree(&repo.dir).unwrap();
+
+        let paths: Vec<String> = hunks(&repo.dir, &base, &tree).unwrap().into_iter().map(|(p, _)| p).collect();
+        assert_eq!(paths, ["a.txt", "dir b/x.txt", "quote\"d.txt", "tab\there.txt"]);
+
+        let stats: Vec<String> = numstat(&repo.dir, &base, &tree).unwrap().into_iter().map(|s| s.path).collect();
+        assert_eq!(stats, paths);
+    }
+}
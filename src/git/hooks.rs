@@ -0,0 +1,96 @@
+// Worktree-scoped git hooks
+//
+// Hooks are installed into a directory inside the worktree's own git dir
+// and enabled through `core.hooksPath` in the worktree config, so the main
+// checkout and other sessions are unaffected. Existing repository hooks
+// keep running: they are linked in, and acta's hooks call them afterwards.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+pub struct Hook {
+    pub name: &'static str,
+    /// Shell snippet run before the repository's own hook of the same name
+    pub body: String,
+    /// Capture stdin into `$input` for the snippet; it is replayed to the
+    /// repository's hook
+    pub reads_stdin: bool,
+}
+
+/// Install `hooks` for `worktree` only
+pub fn install(worktree: &Path, hooks: &[Hook]) -> Result<()> {
+    if hooks.is_empty() {
+        return Ok(());
+    }
+
+    // Resolve before core.hooksPath points at our directory
+    let original = super::git_path(worktree, "hooks")?;
+    let dir = super::git_path(worktree, "acta-hooks")?;
+    fs::create_dir_all(&dir).context("Failed to create hooks directory")?;
+
+    if let Ok(entries) = fs::read_dir(&original) {
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let is_sample = name.to_string_lossy().ends_with(".sample");
+            if is_sample || hooks.iter().any(|h| name == h.name) {
+                continue;
+            }
+            link(&entry.path(), &dir.join(&name))?;
+        }
+    }
+
+    for hook in hooks {
+        let chained = quote(&original.join(hook.name).to_string_lossy());
+        let script = if hook.reads_stdin {
+            format!(
+                "#!/bin/sh\n# Installed by acta\ninput=$(cat)\n{}\nif [ -x {} ]; then\n  printf '%s\\n' \"$input\" | {} \"$@\"\nfi\n",
+                hook.body.trim_end(),
+                chained,
+                chained
+            )
+        } else {
+            format!(
+                "#!/bin/sh\n# Installed by acta\n{}\nif [ -x {} ]; then\n  exec {} \"$@\"\nfi\n",
+                hook.body.trim_end(),
+                chained,
+                chained
+            )
+        };
+        let path = dir.join(hook.name);
+        fs::write(&path, script)
+            .with_context(|| format!("Failed to write {} hook", hook.name))?;
+        make_executable(&path)?;
+    }
+
+    super::set_worktree_config(worktree, "core.hooksPath", &dir.to_string_lossy())
+}
+
+#[cfg(unix)]
+fn link(src: &Path, dst: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(src, dst)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn link(src: &Path, dst: &Path) -> Result<()> {
+    fs::copy(src, dst)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn make_executable(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// Shell-quote `value` for use inside a hook script
+pub fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
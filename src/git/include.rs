@@ -0,0 +1,220 @@
+// Bring untracked project files (.env, node_modules, certificates, ...) into
+// freshly created worktrees
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{debug, warn};
+use crate::config::{IncludeMode, IncludeRule};
+
+/// Directories never searched for include matches
+const SKIP_DIRS: &[&str] = &[".git", ".acta"];
+
+/// Apply include rules from `repo` into `worktree`, skipping the `nested`
+/// directory holding session worktrees inside the repository. Paths that
+/// already exist in the worktree (e.g. tracked files) are left alone.
+/// Returns the relative paths that were brought over.
+pub fn apply(repo: &Path, worktree: &Path, rules: &[IncludeRule], nested: Option<&Path>) -> Result<Vec<PathBuf>> {
+    let mut applied: Vec<PathBuf> = Vec::new();
+
+    for rule in rules {
+        rule.check()?;
+        let pattern = format!(
+            "{}/{}",
+            glob::Pattern::escape(&repo.to_string_lossy()),
+            rule.pattern
+        );
+        let matches = glob::glob(&pattern)
+            .with_context(|| format!("Invalid include pattern '{}'", rule.pattern))?;
+
+        for path in matches.filter_map(|m| m.ok()) {
+            if nested.is_some_and(|n| path.starts_with(n)) {
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(repo) else {
+                continue;
+            };
+            let relative = relative.to_path_buf();
+
+            let skipped = relative
+                .components()
+                .next()
+                .map(|c| SKIP_DIRS.iter().any(|d| c.as_os_str() == *d))
+                .unwrap_or(true);
+            if skipped || applied.iter().any(|a| relative.starts_with(a)) {
+                continue;
+            }
+
+            let target = worktree.join(&relative);
+            if target.symlink_metadata().is_ok() {
+                debug!("Skipping {}, already present in worktree", relative.display());
+                continue;
+            }
+
+            copy_path(&path, &target, rule.mode)
+                .with_context(|| format!("Failed to include {}", relative.display()))?;
+            applied.push(relative);
+        }
+    }
+
+    Ok(applied)
+}
+
+/// Copy, symlink or reflink `src` to `dst`, creating parent directories
+pub fn copy_path(src: &Path, dst: &Path, mode: IncludeMode) -> Result<()> {
+    if let Some(parent) = dst.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    match mode {
+        IncludeMode::Symlink => symlink(&std::path::absolute(src)?, dst),
+        IncludeMode::Reflink => {
+            if reflink(src, dst) {
+                Ok(())
+            } else {
+                warn!("Reflink not supported for {}, copying instead", src.display());
+                copy_recursive(src, dst)
+            }
+        }
+        IncludeMode::Copy => copy_recursive(src, dst),
+    }
+}
+
+fn copy_recursive(src: &Path, dst: &Path) -> Result<()> {
+    let meta = src.symlink_metadata()?;
+
+    if meta.file_type().is_symlink() {
+        symlink(&fs::read_link(src)?, dst)
+    } else if meta.is_dir() {
+        fs::create_dir_all(dst)?;
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &dst.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(src, dst)?;
+        Ok(())
+    }
+}
+
+/// Copy-on-write clone via the platform `cp`; false if unsupported
+fn reflink(src: &Path, dst: &Path) -> bool {
+    let flag = if cfg!(target_os = "macos") {
+        "-cR"
+    } else {
+        "--reflink=always"
+    };
+
+    let mut cmd = Command::new("cp");
+    cmd.arg(flag);
+    if !cfg!(target_os = "macos") {
+        cmd.arg("-R");
+    }
+
+    let ok = cmd
+        .arg(src)
+        .arg(dst)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+
+    if !ok {
+        // cp may leave a partial copy behind
+        let _ = fs::remove_dir_all(dst).or_else(|_| fs::remove_file(dst));
+    }
+    ok
+}
+
+#[cfg(unix)]
+fn symlink(src: &Path, dst: &Path) -> Result<()> {
+    std::os::unix::fs::symlink(src, dst)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn symlink(src: &Path, dst: &Path) -> Result<()> {
+    if src.is_dir() {
+        std::os::windows::fs::symlink_dir(src, dst)?;
+    } else {
+        std::os::windows::fs::symlink_file(src, dst)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProjectConfig;
+    use crate::git::testing::write;
+
+    /// A project with an untracked `node_modules`, a session worktree
+    /// nested under `.worktrees` with its own, and a fresh worktree
+    struct Scratch {
+        root: PathBuf,
+        repo: PathBuf,
+        worktree: PathBuf,
+    }
+
+    impl Scratch {
+        fn new() -> Self {
+            let root = std::env::temp_dir().join(format!("acta-test-{}", uuid::Uuid::new_v4()));
+            let (repo, worktree) = (root.join("repo"), root.join("worktree"));
+            write(&repo, "node_modules/pkg/index.js", "module\n");
+            write(&repo, ".env", "KEY=1\n");
+            write(&repo, ".worktrees/other/node_modules/pkg/index.js", "other\n");
+            write(&repo, ".worktrees/other/.env", "KEY=2\n");
+            write(&root, "secret.txt", "secret\n");
+            fs::create_dir_all(&worktree).unwrap();
+            Self { root, repo, worktree }
+        }
+
+        fn apply(&self, patterns: &[&str]) -> Result<Vec<PathBuf>> {
+            let rules: Vec<IncludeRule> = patterns
+                .iter()
+                .map(|p| IncludeRule {
+                    pattern: p.to_string(),
+                    mode: IncludeMode::Copy,
+                })
+                .collect();
+            apply(&self.repo, &self.worktree, &rules, Some(&self.repo.join(".worktrees")))
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    #[test]
+    fn nested_worktrees_are_not_included() {
+        let scratch = Scratch::new();
+        let mut applied = scratch.apply(&["**/node_modules", ".*"]).unwrap();
+        applied.sort();
+
+        assert_eq!(applied, [PathBuf::from(".env"), PathBuf::from("node_modules")]);
+        assert!(scratch.worktree.join("node_modules/pkg/index.js").exists());
+        assert!(!scratch.worktree.join(".worktrees").exists());
+    }
+
+    #[test]
+    fn patterns_leaving_the_repository_are_refused() {
+        let scratch = Scratch::new();
+        for pattern in ["../secret.txt", "node_modules/../../*.txt", "/etc/hostname"] {
+            let e = scratch.apply(&[pattern]).unwrap_err();
+            assert!(e.to_string().contains("must be relative to the repository root"), "{}", pattern);
+        }
+        assert_eq!(fs::read_dir(&scratch.worktree).unwrap().count(), 0);
+        assert!(scratch.apply(&["./.env"]).is_ok());
+    }
+
+    #[test]
+    fn project_config_with_escaping_patterns_fails_to_load() {
+        let scratch = Scratch::new();
+        write(&scratch.repo, ProjectConfig::FILE_NAME, "worktree:\n  include:\n    - pattern: ../secret.txt\n");
+        let e = ProjectConfig::load(&scratch.repo).unwrap_err();
+        assert!(format!("{:#}", e).contains("Include pattern '../secret.txt' must be relative"));
+    }
+}
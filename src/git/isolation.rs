@@ -0,0 +1,166 @@
+// Workspace isolation backends
+//
+// A backend owns the directory an agent works in: creating it, tearing it
+// down, reporting what changed and bringing the changes back.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use super::copy::DirectoryCopy;
+#[cfg(test)]
+use super::memory::InMemory;
+use super::worktree::GitWorktree;
+use super::{checkpoint, diff};
+use crate::config::{IncludeRule, SubmoduleMode};
+use crate::session::Session;
+
+/// Which backend owns a session workspace
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    #[default]
+    Worktree,
+    Copy,
+    /// Process-local fake for tests; never written to session state
+    #[cfg(test)]
+    Memory,
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BackendKind::Worktree => "worktree",
+            BackendKind::Copy => "copy",
+            #[cfg(test)]
+            BackendKind::Memory => "memory",
+        })
+    }
+}
+
+/// What to check out in a new session worktree
+#[derive(Debug, Clone, Default)]
+pub enum Checkout {
+    /// A fresh `acta/<id>` branch from the current HEAD
+    #[default]
+    NewBranch,
+    /// A fresh `acta/<id>` branch from another branch or commit
+    Base(String),
+    /// An existing local or remote-tracking branch
+    Branch(String),
+    /// A commit on a detached HEAD
+    Detached(String),
+}
+
+/// Everything a backend needs to create a workspace
+pub struct WorkspaceSpec<'a> {
+    pub id: &'a str,
+    /// Repository root, or the project directory for copies
+    pub source: &'a Path,
+    /// Where the workspace should live
+    pub path: &'a Path,
+    pub checkout: &'a Checkout,
+    pub include: &'a [IncludeRule],
+    pub ignore: &'a [String],
+    /// Directory inside `source` holding session workspaces
+    pub nested: Option<&'a Path>,
+    /// Cone-mode sparse checkout paths; everything when empty
+    pub sparse: &'a [String],
+    pub submodules: SubmoduleMode,
+}
+
+/// A freshly created workspace
+pub struct Workspace {
+    pub branch: Option<String>,
+    pub base_commit: Option<String>,
+    /// Whether `branch` was created for the session and can be deleted
+    pub owns_branch: bool,
+}
+
+/// Changes in a workspace relative to its base
+pub struct Diff {
+    /// `git diff --stat` style summary
+    pub stat: String,
+    pub patch: String,
+}
+
+pub struct WorkspaceStatus {
+    pub exists: bool,
+    pub changed_files: usize,
+}
+
+pub enum MergeOutcome {
+    Merged { files: usize, into: String },
+    NothingToMerge,
+    Conflicts(Vec<String>),
+}
+
+pub trait Isolation {
+    fn kind(&self) -> BackendKind;
+
+    fn create(&self, spec: &WorkspaceSpec) -> Result<Workspace>;
+
+    fn remove(&self, session: &Session) -> Result<()>;
+
+    /// Committed and uncommitted changes since the session's base
+    fn diff(&self, session: &Session, color: bool) -> Result<Diff>;
+
+    fn status(&self, session: &Session) -> Result<WorkspaceStatus>;
+
+    /// Bring the session's work back into the original project
+    fn merge(&self, session: &Session) -> Result<MergeOutcome>;
+
+    /// Directory git commands run in for the session, or None when the
+    /// workspace is not a git checkout. Copies qualify through their
+    /// private git directory.
+    fn git_dir<'a>(&self, session: &'a Session) -> Option<&'a Path> {
+        Some(&session.worktree_path)
+    }
+}
+
+/// Backend for `kind`; copies keep their private git dirs under `state_dir`
+pub fn backend(kind: BackendKind, state_dir: &Path) -> Box<dyn Isolation> {
+    match kind {
+        BackendKind::Worktree => Box::new(GitWorktree),
+        BackendKind::Copy => Box::new(DirectoryCopy::new(state_dir)),
+        #[cfg(test)]
+        BackendKind::Memory => Box::new(InMemory),
+    }
+}
+
+/// Diff of everything in a git-tracked workspace against the session base
+pub(super) fn git_diff(session: &Session, color: bool) -> Result<Diff> {
+    let (worktree, base) = git_base(session)?;
+    let tree = checkpoint::snapshot_tree(&worktree)?;
+    Ok(Diff {
+        stat: diff::stat(&worktree, base, &tree, color)?,
+        patch: diff::patch(&worktree, base, &tree, color)?,
+    })
+}
+
+pub(super) fn git_status(session: &Session) -> Result<WorkspaceStatus> {
+    let (worktree, base) = git_base(session)?;
+    if !worktree.exists() {
+        return Ok(WorkspaceStatus {
+            exists: false,
+            changed_files: 0,
+        });
+    }
+    let tree = checkpoint::snapshot_tree(&worktree)?;
+    Ok(WorkspaceStatus {
+        exists: true,
+        changed_files: diff::numstat(&worktree, base, &tree)?.len(),
+    })
+}
+
+fn git_base(session: &Session) -> Result<(PathBuf, &str)> {
+    let worktree = std::path::absolute(&session.worktree_path)?;
+    let base = session
+        .base_commit
+        .as_deref()
+        .context("Session has no base commit")?;
+    if base.is_empty() {
+        bail!("Session has no base commit");
+    }
+    Ok((worktree, base))
+}
@@ -0,0 +1,125 @@
+// In-memory isolation backend
+//
+// Keeps workspaces as maps of path to contents inside the process so
+// session lifecycle logic can be exercised without real repositories.
+
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use super::diff::FileStat;
+use super::isolation::{
+    BackendKind, Diff, Isolation, MergeOutcome, Workspace, WorkspaceSpec, WorkspaceStatus,
+};
+use crate::session::Session;
+
+#[derive(Default)]
+struct Files {
+    base: BTreeMap<String, String>,
+    current: BTreeMap<String, String>,
+}
+
+fn store() -> MutexGuard<'static, HashMap<String, Files>> {
+    static STORE: OnceLock<Mutex<HashMap<String, Files>>> = OnceLock::new();
+    STORE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+pub struct InMemory;
+
+/// Write a file into a session's workspace, as its agent would
+pub fn write(id: &str, path: &str, contents: &str) -> Result<()> {
+    let mut store = store();
+    let files = store.get_mut(id).context("Workspace not found")?;
+    files.current.insert(path.to_string(), contents.to_string());
+    Ok(())
+}
+
+impl Isolation for InMemory {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Memory
+    }
+
+    fn git_dir<'a>(&self, _session: &'a Session) -> Option<&'a Path> {
+        None
+    }
+
+    fn create(&self, spec: &WorkspaceSpec) -> Result<Workspace> {
+        store().insert(spec.id.to_string(), Files::default());
+        Ok(Workspace {
+            branch: Some(format!("acta/{}", &spec.id[..8])),
+            base_commit: None,
+            owns_branch: true,
+        })
+    }
+
+    fn remove(&self, session: &Session) -> Result<()> {
+        store().remove(&session.id);
+        Ok(())
+    }
+
+    fn diff(&self, session: &Session, _color: bool) -> Result<Diff> {
+        let store = store();
+        let files = store.get(&session.id).context("Workspace not found")?;
+        let stat = changes(files)
+            .iter()
+            .map(|f| format!(" {} | +{} -{}", f.path, f.added, f.removed))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(Diff {
+            stat: stat.clone(),
+            patch: stat,
+        })
+    }
+
+    fn status(&self, session: &Session) -> Result<WorkspaceStatus> {
+        let store = store();
+        Ok(match store.get(&session.id) {
+            Some(files) => WorkspaceStatus {
+                exists: true,
+                changed_files: changes(files).len(),
+            },
+            None => WorkspaceStatus {
+                exists: false,
+                changed_files: 0,
+            },
+        })
+    }
+
+    fn merge(&self, session: &Session) -> Result<MergeOutcome> {
+        let mut store = store();
+        let files = store.get_mut(&session.id).context("Workspace not found")?;
+        let count = changes(files).len();
+        if count == 0 {
+            return Ok(MergeOutcome::NothingToMerge);
+        }
+        files.base = files.current.clone();
+        Ok(MergeOutcome::Merged {
+            files: count,
+            into: "memory".to_string(),
+        })
+    }
+}
+
+/// Whole-file line counts for every path that differs from the base
+fn changes(files: &Files) -> Vec<FileStat> {
+    let lines = |map: &BTreeMap<String, String>, path: &str| {
+        map.get(path).map(|c| c.lines().count()).unwrap_or(0)
+    };
+
+    let mut paths: Vec<&String> = files.base.keys().chain(files.current.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    paths
+        .into_iter()
+        .filter(|p| files.base.get(*p) != files.current.get(*p))
+        .map(|p| FileStat {
+            path: p.clone(),
+            added: lines(&files.current, p),
+            removed: lines(&files.base, p),
+        })
+        .collect()
+}
@@ -1,2 +1,253 @@
 // Git worktree operations module
-// Will handle creating/deleting worktrees for session isolation
+// Handles creating/deleting worktrees for session isolation
+
+pub mod checkpoint;
+pub mod copy;
+pub mod diff;
+pub mod hooks;
+pub mod include;
+pub mod isolation;
+#[cfg(test)]
+pub mod memory;
+pub mod submodule;
+pub mod sync;
+#[cfg(test)]
+pub mod testing;
+pub mod worktree;
+
+use anyhow::{bail, Context, Result};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Run a git command in `dir` and return its trimmed stdout
+pub fn run(dir: &Path, args: &[&str]) -> Result<String> {
+    run_with_env(dir, args, &[])
+}
+
+/// Run a git command in `dir` with extra environment variables
+pub fn run_with_env(dir: &Path, args: &[&str], env: &[(&str, &OsStr)]) -> Result<String> {
+    let mut cmd = Command::new("git");
+    cmd.arg("-C").arg(dir).args(args);
+    for (key, value) in env {
+        cmd.env(key, value);
+    }
+
+    let output = cmd
+        .output()
+        .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim_end().to_string())
+}
+
+/// Find the top-level directory of the repository containing `dir`
+pub fn repo_root(dir: &Path) -> Option<PathBuf> {
+    run(dir, &["rev-parse", "--show-toplevel"])
+        .ok()
+        .map(PathBuf::from)
+}
+
+/// Resolve a path inside the git directory of `worktree` (e.g. `index`,
+/// `MERGE_HEAD`), which differs per linked worktree
+pub fn git_path(worktree: &Path, name: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(run(worktree, &["rev-parse", "--git-path", name])?);
+    if path.as_os_str().is_empty() {
+        bail!("Could not resolve git path for {}", name);
+    }
+    Ok(if path.is_absolute() {
+        path
+    } else {
+        worktree.join(path)
+    })
+}
+
+/// Name of the branch checked out in `dir`, None when detached
+pub fn current_branch(dir: &Path) -> Option<String> {
+    run(dir, &["symbolic-ref", "--quiet", "--short", "HEAD"]).ok()
+}
+
+/// Resolve a revision to a full commit hash
+pub fn rev_parse(dir: &Path, rev: &str) -> Result<String> {
+    run(dir, &["rev-parse", "--verify", &format!("{}^{{commit}}", rev)])
+}
+
+/// Create a worktree at `path` on a new branch starting at `base`
+pub fn create_worktree(repo: &Path, path: &Path, branch: &str, base: &str, checkout: bool) -> Result<()> {
+    let path = path.to_string_lossy();
+    add_worktree(repo, &["-b", branch, &path, base], checkout)
+        .with_context(|| format!("Failed to create worktree at {}", path))
+}
+
+/// Create a worktree at `path` with an existing local branch checked out
+pub fn checkout_worktree(repo: &Path, path: &Path, branch: &str, checkout: bool) -> Result<()> {
+    let path = path.to_string_lossy();
+    add_worktree(repo, &[&path, branch], checkout)
+        .with_context(|| format!("Failed to check out {} at {}", branch, path))
+}
+
+/// Create a worktree with a new local `branch` tracking `upstream`
+pub fn track_worktree(
+    repo: &Path,
+    path: &Path,
+    branch: &str,
+    upstream: &str,
+    checkout: bool,
+) -> Result<()> {
+    let path = path.to_string_lossy();
+    add_worktree(repo, &["--track", "-b", branch, &path, upstream], checkout)
+        .with_context(|| format!("Failed to check out {} at {}", upstream, path))
+}
+
+/// Create a worktree with `commit` checked out on a detached HEAD
+pub fn detached_worktree(repo: &Path, path: &Path, commit: &str, checkout: bool) -> Result<()> {
+    let path = path.to_string_lossy();
+    add_worktree(repo, &["--detach", &path, commit], checkout)
+        .with_context(|| format!("Failed to check out {} at {}", commit, path))
+}
+
+/// `git worktree add`; without `checkout` only HEAD is set up, leaving the
+/// files to be populated later (e.g. after configuring a sparse checkout)
+fn add_worktree(repo: &Path, args: &[&str], checkout: bool) -> Result<()> {
+    let mut full = vec!["worktree", "add"];
+    if !checkout {
+        full.push("--no-checkout");
+    }
+    full.extend_from_slice(args);
+    run(repo, &full)?;
+    Ok(())
+}
+
+/// Restrict `worktree` to the cone-mode `paths` and populate it
+pub fn sparse_checkout(worktree: &Path, paths: &[String]) -> Result<()> {
+    let mut args = vec!["sparse-checkout", "set", "--cone", "--"];
+    args.extend(paths.iter().map(String::as_str));
+    run(worktree, &args).context("Failed to configure sparse checkout")?;
+    run(worktree, &["checkout", "--quiet"])?;
+    Ok(())
+}
+
+/// True when only part of the tree is checked out in `worktree`
+pub fn is_sparse(worktree: &Path) -> bool {
+    run(worktree, &["config", "--bool", "core.sparseCheckout"]).ok().as_deref() == Some("true")
+}
+
+pub fn branch_exists(repo: &Path, branch: &str) -> bool {
+    run(repo, &["show-ref", "--verify", "--quiet", &format!("refs/heads/{}", branch)]).is_ok()
+}
+
+/// Remote-tracking branches named `branch` on any remote (e.g. `origin/x`)
+pub fn remote_branches(repo: &Path, branch: &str) -> Result<Vec<String>> {
+    let refs = run(repo, &["for-each-ref", "--format=%(refname:short)", "refs/remotes/"])?;
+    Ok(refs
+        .lines()
+        .filter(|r| r.split_once('/').map(|(_, b)| b == branch).unwrap_or(false))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Path of the worktree that has `branch` checked out, if any
+pub fn worktree_for_branch(repo: &Path, branch: &str) -> Result<Option<PathBuf>> {
+    let output = run(repo, &["worktree", "list", "--porcelain"])?;
+    let target = format!("branch refs/heads/{}", branch);
+
+    let mut current: Option<&str> = None;
+    for line in output.lines() {
+        if let Some(path) = line.strip_prefix("worktree ") {
+            current = Some(path);
+        } else if line == target {
+            return Ok(current.map(PathBuf::from));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Remove a worktree and delete its branch
+pub fn remove_worktree(repo: &Path, path: &Path, branch: Option<&str>) -> Result<()> {
+    let path = std::path::absolute(path)?;
+    if path.exists() {
+        run(repo, &["worktree", "remove", "--force", &path.to_string_lossy()])
+            .with_context(|| format!("Failed to remove worktree at {}", path.display()))?;
+    } else {
+        run(repo, &["worktree", "prune"])?;
+    }
+
+    if let Some(branch) = branch {
+        run(repo, &["branch", "-D", branch])
+            .with_context(|| format!("Failed to delete branch {}", branch))?;
+    }
+
+    Ok(())
+}
+
+/// Merge `branch` into whatever is checked out in `repo` with a merge
+/// commit. On conflicts the merge is aborted and the files are returned.
+pub fn merge_branch(repo: &Path, branch: &str) -> Result<Vec<String>> {
+    let env = [("GIT_EDITOR", OsStr::new("true"))];
+    if run_with_env(repo, &["merge", "--no-ff", "--no-edit", branch], &env).is_ok() {
+        return Ok(vec![]);
+    }
+
+    let conflicts = sync::conflicted_files(repo)?;
+    let _ = run(repo, &["merge", "--abort"]);
+    if conflicts.is_empty() {
+        bail!("Failed to merge {}", branch);
+    }
+    Ok(conflicts)
+}
+
+/// True when the worktree has uncommitted changes (untracked files included)
+pub fn is_dirty(dir: &Path) -> Result<bool> {
+    Ok(!run(dir, &["status", "--porcelain"])?.is_empty())
+}
+
+/// Set `key` in the config of `worktree` alone, enabling per-worktree
+/// config for the repository if needed
+pub fn set_worktree_config(worktree: &Path, key: &str, value: &str) -> Result<()> {
+    if run(worktree, &["config", "--bool", "extensions.worktreeConfig"]).ok().as_deref() != Some("true") {
+        run(worktree, &["config", "extensions.worktreeConfig", "true"])?;
+    }
+    run(worktree, &["config", "--worktree", key, value])
+        .with_context(|| format!("Failed to set {} for worktree", key))?;
+    Ok(())
+}
+
+/// Add `pattern` to the repository's `info/exclude` unless already listed
+pub fn exclude(repo: &Path, pattern: &str) -> Result<()> {
+    let path = PathBuf::from(run(repo, &["rev-parse", "--path-format=absolute", "--git-common-dir"])?)
+        .join("info")
+        .join("exclude");
+    let existing = std::fs::read_to_string(&path).unwrap_or_default();
+    if existing.lines().any(|l| l.trim() == pattern) {
+        return Ok(());
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut contents = existing;
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    contents.push_str(pattern);
+    contents.push('\n');
+    std::fs::write(&path, contents)
+        .with_context(|| format!("Failed to update {}", path.display()))
+}
+
+/// Delete every ref under `prefix` (e.g. `refs/acta/<id>/`)
+pub fn delete_refs(repo: &Path, prefix: &str) -> Result<()> {
+    let refs = run(repo, &["for-each-ref", "--format=%(refname)", prefix])?;
+    for refname in refs.lines().filter(|l| !l.is_empty()) {
+        run(repo, &["update-ref", "-d", refname])?;
+    }
+    Ok(())
+}
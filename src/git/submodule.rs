@@ -0,0 +1,70 @@
+// Submodules in session worktrees
+//
+// Linked worktrees start with empty submodule directories. Each submodule is
+// initialised from the object store the main checkout already has for it,
+// so only commits missing there are fetched.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+use crate::config::SubmoduleMode;
+
+/// Initialise and update the submodules of `worktree`, borrowing objects
+/// from the matching submodules checked out in `main`
+pub fn update(main: &Path, worktree: &Path, mode: SubmoduleMode) -> Result<()> {
+    if mode == SubmoduleMode::None {
+        return Ok(());
+    }
+
+    for path in paths(worktree)? {
+        let target = worktree.join(&path);
+        // Outside a sparse checkout's cone
+        if !target.exists() {
+            continue;
+        }
+
+        let mut args = vec!["submodule", "update", "--init", "--quiet"];
+        let reference = module_dir(&main.join(&path));
+        if let Some(reference) = &reference {
+            debug!("Reusing objects from {} for submodule {}", reference.display(), path);
+            args.push("--reference");
+            args.push(reference.to_str().unwrap_or_default());
+        }
+        args.push("--");
+        args.push(&path);
+        super::run(worktree, &args)?;
+
+        if mode == SubmoduleMode::Recursive {
+            update(&main.join(&path), &target, mode)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Submodule paths registered in `.gitmodules`
+fn paths(dir: &Path) -> Result<Vec<String>> {
+    if !dir.join(".gitmodules").exists() {
+        return Ok(vec![]);
+    }
+    let output = super::run(
+        dir,
+        &["config", "--file", ".gitmodules", "--get-regexp", r"^submodule\..*\.path$"],
+    )
+    .unwrap_or_default();
+
+    Ok(output
+        .lines()
+        .filter_map(|line| line.split_once(' ').map(|(_, path)| path.to_string()))
+        .collect())
+}
+
+/// Git directory of an initialised submodule checkout
+fn module_dir(checkout: &Path) -> Option<PathBuf> {
+    if !checkout.join(".git").exists() {
+        return None;
+    }
+    super::run(checkout, &["rev-parse", "--path-format=absolute", "--git-dir"])
+        .ok()
+        .map(PathBuf::from)
+}
@@ -0,0 +1,215 @@
+// Bring a session branch up to date with its base branch
+
+use anyhow::{bail, Context, Result};
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    Merge,
+    Rebase,
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Strategy::Merge => "merge",
+            Strategy::Rebase => "rebase",
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum Outcome {
+    UpToDate,
+    Updated,
+    /// Stopped on conflicts; the worktree is left mid-merge/rebase
+    Conflicted(Vec<String>),
+}
+
+/// Never open an editor for merge commits or rebase messages
+const NO_EDITOR: [(&str, &str); 1] = [("GIT_EDITOR", "true")];
+
+fn run(worktree: &Path, args: &[&str]) -> Result<String> {
+    let env: Vec<(&str, &OsStr)> = NO_EDITOR.iter().map(|(k, v)| (*k, OsStr::new(v))).collect();
+    super::run_with_env(worktree, args, &env)
+}
+
+/// Merge or rebase the worktree's branch onto `base`. Uncommitted changes
+/// are stashed and reapplied around the operation.
+pub fn sync(worktree: &Path, base: &str, strategy: Strategy) -> Result<Outcome> {
+    if let Some(op) = in_progress(worktree)? {
+        bail!("A {} is already in progress, continue or abort it first", op);
+    }
+
+    if super::run(worktree, &["merge-base", "--is-ancestor", base, "HEAD"]).is_ok() {
+        return Ok(Outcome::UpToDate);
+    }
+
+    let result = match strategy {
+        Strategy::Merge => run(worktree, &["merge", "--no-edit", "--autostash", base]),
+        Strategy::Rebase => run(worktree, &["rebase", "--autostash", base]),
+    };
+
+    finish(worktree, result)
+}
+
+/// Resume after conflicts were resolved and staged. Nothing is staged on
+/// the user's behalf, and conflict markers left in files stop it.
+pub fn resume(worktree: &Path) -> Result<Outcome> {
+    let Some(strategy) = in_progress(worktree)? else {
+        bail!("No merge or rebase in progress");
+    };
+
+    let unmerged = conflicted_files(worktree)?;
+    if !unmerged.is_empty() {
+        bail!(
+            "Unresolved conflicts in {}; resolve them and stage the files with 'git add'",
+            unmerged.join(", ")
+        );
+    }
+    let markers = leftover_markers(worktree)?;
+    if !markers.is_empty() {
+        bail!("Conflict markers left in {}; remove them and stage the files again", markers.join(", "));
+    }
+
+    let result = match strategy {
+        Strategy::Merge => run(worktree, &["commit", "--no-edit"]),
+        Strategy::Rebase => run(worktree, &["rebase", "--continue"]),
+    };
+
+    finish(worktree, result)
+}
+
+/// Abandon an in-progress merge or rebase
+pub fn abort(worktree: &Path) -> Result<()> {
+    match in_progress(worktree)? {
+        Some(Strategy::Merge) => super::run(worktree, &["merge", "--abort"])?,
+        Some(Strategy::Rebase) => super::run(worktree, &["rebase", "--abort"])?,
+        None => bail!("No merge or rebase in progress"),
+    };
+    Ok(())
+}
+
+/// Which operation, if any, is stopped in the worktree
+pub fn in_progress(worktree: &Path) -> Result<Option<Strategy>> {
+    for dir in ["rebase-merge", "rebase-apply"] {
+        if super::git_path(worktree, dir)?.exists() {
+            return Ok(Some(Strategy::Rebase));
+        }
+    }
+    if super::git_path(worktree, "MERGE_HEAD")?.exists() {
+        return Ok(Some(Strategy::Merge));
+    }
+    Ok(None)
+}
+
+/// Files with unresolved conflicts
+pub fn conflicted_files(worktree: &Path) -> Result<Vec<String>> {
+    let output = super::run(worktree, &["diff", "--name-only", "--diff-filter=U"])?;
+    Ok(output.lines().map(str::to_string).collect())
+}
+
+/// Files whose staged or unstaged changes still contain conflict markers
+fn leftover_markers(worktree: &Path) -> Result<Vec<String>> {
+    let mut files: Vec<String> = Vec::new();
+    for args in [&["diff", "--check"][..], &["diff", "--check", "--cached"]] {
+        // Fails whenever it reports something, whitespace errors included,
+        // so the report is read whatever the exit status
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(worktree)
+            .args(args)
+            .output()
+            .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let Some((file, _line)) = line
+                .strip_suffix(": leftover conflict marker")
+                .and_then(|l| l.rsplit_once(':'))
+            else {
+                continue;
+            };
+            if !files.iter().any(|f| f == file) {
+                files.push(file.to_string());
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn finish(worktree: &Path, result: Result<String>) -> Result<Outcome> {
+    match result {
+        Ok(_) if in_progress(worktree)?.is_none() => Ok(Outcome::Updated),
+        Ok(_) => Ok(Outcome::Conflicted(conflicted_files(worktree)?)),
+        Err(e) => {
+            let files = conflicted_files(worktree)?;
+            if files.is_empty() && in_progress(worktree)?.is_none() {
+                Err(e)
+            } else {
+                Ok(Outcome::Conflicted(files))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::testing::Repo;
+
+    /// A session branch and master both changing `a.txt`, synced with
+    /// `strategy` and stopped on the conflict
+    fn conflicted(strategy: Strategy) -> Repo {
+        let repo = Repo::new();
+        repo.git(&["checkout", "-q", "-b", "session"]);
+        repo.write("a.txt", "session\n");
+        repo.commit("session edit");
+        repo.git(&["checkout", "-q", "master"]);
+        repo.write("a.txt", "base\n");
+        repo.commit("base edit");
+        repo.git(&["checkout", "-q", "session"]);
+
+        let outcome = sync(&repo.dir, "master", strategy).unwrap();
+        assert!(matches!(outcome, Outcome::Conflicted(files) if files == ["a.txt"]));
+        repo
+    }
+
+    #[test]
+    fn continuing_with_markers_is_refused() {
+        for strategy in [Strategy::Merge, Strategy::Rebase] {
+            let repo = conflicted(strategy);
+            let head = repo.git(&["rev-parse", "HEAD"]);
+
+            // Nothing staged yet
+            let e = resume(&repo.dir).unwrap_err();
+            assert!(e.to_string().starts_with("Unresolved conflicts in a.txt"), "{}", e);
+
+            // Staged as is, markers and all
+            repo.git(&["add", "a.txt"]);
+            let e = resume(&repo.dir).unwrap_err();
+            assert!(e.to_string().starts_with("Conflict markers left in a.txt"), "{}", e);
+            assert_eq!(in_progress(&repo.dir).unwrap(), Some(strategy));
+            assert_eq!(repo.git(&["rev-parse", "HEAD"]), head);
+
+            repo.write("a.txt", "both\n");
+            repo.git(&["add", "a.txt"]);
+            assert!(matches!(resume(&repo.dir).unwrap(), Outcome::Updated));
+            assert_eq!(repo.git(&["show", "HEAD:a.txt"]), "both");
+        }
+    }
+
+    #[test]
+    fn unstaged_resolutions_are_not_committed() {
+        let repo = conflicted(Strategy::Merge);
+        repo.write("a.txt", "both\n");
+        repo.git(&["add", "a.txt"]);
+        repo.write("a.txt", "both\nand more\n");
+        repo.write("other.txt", "untracked\n");
+
+        assert!(matches!(resume(&repo.dir).unwrap(), Outcome::Updated));
+        assert_eq!(repo.git(&["show", "HEAD:a.txt"]), "both");
+        assert_eq!(repo.git(&["status", "--porcelain"]), " M a.txt\n?? other.txt");
+    }
+}
@@ -0,0 +1,72 @@
+// Scratch git repositories for tests
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Repository with one commit of `a.txt` in a temporary directory, removed
+/// with everything next to it on drop
+pub struct Repo {
+    root: PathBuf,
+    pub dir: PathBuf,
+}
+
+impl Repo {
+    pub fn new() -> Self {
+        let root = std::env::temp_dir().join(format!("acta-test-{}", Uuid::new_v4()));
+        let repo = Self {
+            dir: root.join("repo"),
+            root,
+        };
+        fs::create_dir_all(&repo.dir).unwrap();
+        repo.git(&["init", "-q"]);
+        repo.git(&["config", "user.name", "Test"]);
+        repo.git(&["config", "user.email", "test@example.com"]);
+        repo.git(&["config", "commit.gpgsign", "false"]);
+        repo.write("a.txt", "one\n");
+        repo.commit("first");
+        repo
+    }
+
+    pub fn git(&self, args: &[&str]) -> String {
+        super::run(&self.dir, args).unwrap()
+    }
+
+    pub fn write(&self, path: &str, contents: &str) {
+        write(&self.dir, path, contents);
+    }
+
+    pub fn read(&self, path: &str) -> Option<String> {
+        fs::read_to_string(self.dir.join(path)).ok()
+    }
+
+    pub fn commit(&self, message: &str) {
+        self.git(&["add", "-A"]);
+        self.git(&["commit", "-qm", message]);
+    }
+
+    /// A path next to the repository, removed with it
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.root.join(name)
+    }
+
+    /// A linked worktree on a new branch from HEAD
+    pub fn worktree(&self, name: &str) -> PathBuf {
+        let path = self.path(name);
+        self.git(&["worktree", "add", "-q", "-b", name, &path.to_string_lossy()]);
+        path
+    }
+}
+
+impl Drop for Repo {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Write a file under `dir`, creating parent directories
+pub fn write(dir: &Path, path: &str, contents: &str) {
+    let path = dir.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
@@ -0,0 +1,262 @@
+// Linked git worktrees, the default isolation backend
+
+use anyhow::{bail, Context, Result};
+use tracing::{info, warn};
+use std::path::Path;
+use super::isolation::{
+    self, BackendKind, Checkout, Diff, Isolation, MergeOutcome, Workspace, WorkspaceSpec,
+    WorkspaceStatus,
+};
+use crate::session::Session;
+
+pub struct GitWorktree;
+
+impl Isolation for GitWorktree {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Worktree
+    }
+
+    fn create(&self, spec: &WorkspaceSpec) -> Result<Workspace> {
+        let repo = spec.source;
+        let worktree = spec.path;
+        // Sparse worktrees are populated once the cone is configured
+        let checkout = spec.sparse.is_empty();
+
+        let (branch, owns_branch) = match spec.checkout {
+            Checkout::NewBranch => {
+                let branch = format!("acta/{}", &spec.id[..8]);
+                super::create_worktree(repo, worktree, &branch, "HEAD", checkout)?;
+                (Some(branch), true)
+            }
+            Checkout::Base(base) => {
+                let branch = format!("acta/{}", &spec.id[..8]);
+                super::create_worktree(repo, worktree, &branch, base, checkout)?;
+                (Some(branch), true)
+            }
+            Checkout::Branch(requested) => {
+                (Some(checkout_branch(repo, worktree, requested, checkout)?), false)
+            }
+            Checkout::Detached(commit) => {
+                super::detached_worktree(repo, worktree, commit, checkout)?;
+                (None, false)
+            }
+        };
+
+        let cleanup = |e: anyhow::Error| {
+            let owned = branch.as_deref().filter(|_| owns_branch);
+            let _ = super::remove_worktree(repo, worktree, owned);
+            e
+        };
+
+        if !checkout {
+            super::sparse_checkout(worktree, spec.sparse).map_err(cleanup)?;
+        }
+        if let Err(e) = super::submodule::update(repo, worktree, spec.submodules) {
+            warn!("Failed to set up submodules in {}: {:#}", worktree.display(), e);
+        }
+        let base = super::rev_parse(worktree, "HEAD").map_err(cleanup)?;
+        let paths = super::include::apply(repo, worktree, spec.include, spec.nested).map_err(cleanup)?;
+        for path in paths {
+            info!("Included {} in worktree", path.display());
+        }
+
+        Ok(Workspace {
+            branch,
+            base_commit: Some(base),
+            owns_branch,
+        })
+    }
+
+    fn remove(&self, session: &Session) -> Result<()> {
+        let repo = repo(session)?;
+        let branch = session.branch.as_deref().filter(|_| session.owns_branch);
+        super::remove_worktree(repo, &session.worktree_path, branch)?;
+        super::delete_refs(repo, &format!("refs/acta/{}/", session.id))
+    }
+
+    fn diff(&self, session: &Session, color: bool) -> Result<Diff> {
+        isolation::git_diff(session, color)
+    }
+
+    fn status(&self, session: &Session) -> Result<WorkspaceStatus> {
+        isolation::git_status(session)
+    }
+
+    fn merge(&self, session: &Session) -> Result<MergeOutcome> {
+        let repo = repo(session)?;
+        let worktree = std::path::absolute(&session.worktree_path)?;
+        let branch = session
+            .branch
+            .as_deref()
+            .context("Session is on a detached HEAD, nothing to merge (see 'acta diff')")?;
+
+        if super::is_dirty(&worktree)? {
+            bail!(
+                "Session '{}' has uncommitted changes; commit them in {} first",
+                session.display_name(),
+                worktree.display()
+            );
+        }
+
+        let target = super::current_branch(repo).unwrap_or_else(|| "HEAD".to_string());
+        if let Some(base) = &session.base_branch {
+            if *base != target {
+                bail!(
+                    "Repository is on '{}' but the session started from '{}'; check out '{}' first",
+                    target,
+                    base,
+                    base
+                );
+            }
+        }
+
+        let merge_base = super::run(repo, &["merge-base", "HEAD", branch])?;
+        let files = super::diff::numstat(repo, &merge_base, branch)?.len();
+        if files == 0 {
+            return Ok(MergeOutcome::NothingToMerge);
+        }
+
+        let conflicts = super::merge_branch(repo, branch)?;
+        if !conflicts.is_empty() {
+            return Ok(MergeOutcome::Conflicts(conflicts));
+        }
+
+        Ok(MergeOutcome::Merged { files, into: target })
+    }
+}
+
+fn repo(session: &Session) -> Result<&Path> {
+    session
+        .repo_path
+        .as_deref()
+        .context("Session has no repository")
+}
+
+/// Local branch name for `requested` and the remote-tracking branch to
+/// create it from when it only exists on a remote
+pub fn resolve_branch(repo: &Path, requested: &str) -> Result<(String, Option<String>)> {
+    if super::branch_exists(repo, requested) {
+        Ok((requested.to_string(), None))
+    } else if super::rev_parse(repo, &format!("refs/remotes/{}", requested)).is_ok() {
+        // e.g. origin/feature-x
+        let local = requested.split_once('/').map(|(_, b)| b).unwrap_or(requested);
+        Ok((local.to_string(), Some(requested.to_string())))
+    } else {
+        match super::remote_branches(repo, requested)?.as_slice() {
+            [] => bail!("Branch '{}' not found locally or on any remote", requested),
+            [upstream] => Ok((requested.to_string(), Some(upstream.clone()))),
+            many => bail!(
+                "Branch '{}' exists on several remotes ({}), pass one explicitly",
+                requested,
+                many.join(", ")
+            ),
+        }
+    }
+}
+
+/// Check out an existing branch into `worktree`, creating a local branch
+/// for remote-only ones. Returns the local branch name.
+fn checkout_branch(repo: &Path, worktree: &Path, requested: &str, checkout: bool) -> Result<String> {
+    let (local, upstream) = resolve_branch(repo, requested)?;
+
+    if let Some(path) = super::worktree_for_branch(repo, &local)? {
+        bail!("Branch '{}' is already checked out at {}", local, path.display());
+    }
+
+    match upstream {
+        Some(upstream) if !super::branch_exists(repo, &local) => {
+            super::track_worktree(repo, worktree, &local, &upstream, checkout)?
+        }
+        _ => super::checkout_worktree(repo, worktree, &local, checkout)?,
+    }
+
+    Ok(local)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::testing::Repo;
+
+    fn create(repo: &Repo, name: &str, checkout: Checkout) -> Result<Workspace> {
+        let path = repo.path(name);
+        GitWorktree.create(&WorkspaceSpec {
+            id: "0123456789abcdef",
+            source: &repo.dir,
+            path: &path,
+            checkout: &checkout,
+            include: &[],
+            ignore: &[],
+            nested: None,
+            sparse: &[],
+            submodules: Default::default(),
+        })
+    }
+
+    #[test]
+    fn existing_local_branch() {
+        let repo = Repo::new();
+        repo.git(&["branch", "feature"]);
+
+        let workspace = create(&repo, "session", Checkout::Branch("feature".into())).unwrap();
+        assert_eq!(workspace.branch.as_deref(), Some("feature"));
+        assert!(!workspace.owns_branch);
+        let head = crate::git::run(&repo.path("session"), &["symbolic-ref", "HEAD"]).unwrap();
+        assert_eq!(head, "refs/heads/feature");
+    }
+
+    #[test]
+    fn remote_only_branch_gets_a_tracking_branch() {
+        let repo = Repo::new();
+        repo.git(&["branch", "feature"]);
+        let url = repo.dir.to_string_lossy().to_string();
+        repo.git(&["remote", "add", "origin", &url]);
+        repo.git(&["fetch", "-q", "origin"]);
+        repo.git(&["branch", "-D", "feature"]);
+
+        assert_eq!(
+            resolve_branch(&repo.dir, "feature").unwrap(),
+            ("feature".to_string(), Some("origin/feature".to_string()))
+        );
+        assert_eq!(
+            resolve_branch(&repo.dir, "origin/feature").unwrap(),
+            ("feature".to_string(), Some("origin/feature".to_string()))
+        );
+
+        let workspace = create(&repo, "session", Checkout::Branch("origin/feature".into())).unwrap();
+        assert_eq!(workspace.branch.as_deref(), Some("feature"));
+        let upstream = repo.git(&["rev-parse", "--abbrev-ref", "feature@{upstream}"]);
+        assert_eq!(upstream, "origin/feature");
+    }
+
+    #[test]
+    fn detached_commit() {
+        let repo = Repo::new();
+        let first = repo.git(&["rev-parse", "HEAD"]);
+        repo.write("a.txt", "two\n");
+        repo.commit("second");
+
+        let workspace = create(&repo, "session", Checkout::Detached(first.clone())).unwrap();
+        assert_eq!(workspace.branch, None);
+        assert_eq!(workspace.base_commit.as_deref(), Some(first.as_str()));
+        assert!(crate::git::run(&repo.path("session"), &["symbolic-ref", "-q", "HEAD"]).is_err());
+    }
+
+    #[test]
+    fn branch_checked_out_elsewhere_is_refused() {
+        let repo = Repo::new();
+        let other = repo.worktree("feature");
+
+        let err = create(&repo, "session", Checkout::Branch("feature".into()))
+            .err()
+            .unwrap()
+            .to_string();
+        let other = std::fs::canonicalize(other).unwrap();
+        assert!(err.contains("already checked out"), "{}", err);
+        assert!(err.contains(&*other.to_string_lossy()), "{}", err);
+        assert!(!repo.path("session").exists());
+
+        let err = create(&repo, "main", Checkout::Branch("missing".into())).err().unwrap();
+        assert!(err.to_string().contains("not found"), "{}", err);
+    }
+}
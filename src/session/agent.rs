@@ -0,0 +1,173 @@
+// Agent processes running in a pseudo-terminal
+//
+// Whichever acta process shows a session live (the TUI) hosts its agent:
+// the plugin command runs in the session worktree on a PTY and its output is
+// fed through a terminal emulator so it can be drawn into a pane.
+
+use anyhow::{Context, Result};
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::SystemTime;
+use super::Session;
+use crate::config::PluginConfig;
+
+/// Lines of history kept above the visible screen
+const SCROLLBACK: usize = 1000;
+
+pub struct Agent {
+    master: Box<dyn MasterPty + Send>,
+    writer: Box<dyn Write + Send>,
+    child: Box<dyn Child + Send + Sync>,
+    parser: Arc<Mutex<vt100::Parser>>,
+    last_output: Arc<Mutex<Option<SystemTime>>>,
+    size: (u16, u16),
+    /// Claim on the session from `SessionManager::host`, released once
+    /// the agent is gone
+    _host: File,
+}
+
+impl Agent {
+    /// Start the plugin command for `session` in its worktree, holding
+    /// `host` while it runs. `on_output` runs on a reader thread whenever
+    /// output arrives and when it ends.
+    pub fn spawn(
+        session: &Session,
+        plugin: &PluginConfig,
+        host: File,
+        rows: u16,
+        cols: u16,
+        on_output: impl Fn() + Send + 'static,
+    ) -> Result<Self> {
+        let pair = native_pty_system()
+            .openpty(pty_size(rows, cols))
+            .context("Failed to open a pseudo-terminal")?;
+
+        let mut cmd = CommandBuilder::new(&plugin.command);
+        cmd.args(&plugin.args);
+        cmd.args(&session.args);
+        cmd.cwd(&session.worktree_path);
+        for (key, value) in &plugin.env {
+            cmd.env(key, expand_env(value));
+        }
+        cmd.env("ACTA_SESSION", &session.id);
+
+        let child = pair
+            .slave
+            .spawn_command(cmd)
+            .with_context(|| format!("Failed to start '{}'", plugin.command))?;
+        drop(pair.slave);
+
+        let mut reader = pair.master.try_clone_reader()?;
+        let writer = pair.master.take_writer()?;
+        let parser = Arc::new(Mutex::new(vt100::Parser::new(rows, cols, SCROLLBACK)));
+
+        let last_output = Arc::new(Mutex::new(None));
+        let (thread_parser, thread_output) = (parser.clone(), last_output.clone());
+        thread::spawn(move || {
+            let mut buf = [0u8; 8192];
+            while let Ok(n) = reader.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+                lock(&thread_parser).process(&buf[..n]);
+                *lock(&thread_output) = Some(SystemTime::now());
+                on_output();
+            }
+            on_output();
+        });
+
+        Ok(Self {
+            master: pair.master,
+            writer,
+            child,
+            parser,
+            last_output,
+            size: (rows, cols),
+            _host: host,
+        })
+    }
+
+    /// Send raw input to the agent
+    pub fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        self.writer.write_all(bytes)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Type a line of text into the agent, as if submitted by the user
+    pub fn send_line(&mut self, line: &str) -> Result<()> {
+        self.write(line.as_bytes())?;
+        self.write(b"\r")
+    }
+
+    pub fn resize(&mut self, rows: u16, cols: u16) -> Result<()> {
+        if self.size == (rows, cols) || rows == 0 || cols == 0 {
+            return Ok(());
+        }
+        self.master.resize(pty_size(rows, cols))?;
+        lock(&self.parser).screen_mut().set_size(rows, cols);
+        self.size = (rows, cols);
+        Ok(())
+    }
+
+    /// Terminal emulator state holding the agent's screen
+    pub fn parser(&self) -> MutexGuard<'_, vt100::Parser> {
+        lock(&self.parser)
+    }
+
+    /// When the agent last wrote to its terminal
+    pub fn last_output(&self) -> Option<SystemTime> {
+        *lock(&self.last_output)
+    }
+
+    /// Exit code once the process has finished
+    pub fn exit_status(&mut self) -> Option<u32> {
+        self.child.try_wait().ok().flatten().map(|s| s.exit_code())
+    }
+
+    pub fn kill(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl Drop for Agent {
+    fn drop(&mut self) {
+        if self.exit_status().is_none() {
+            self.kill();
+        }
+    }
+}
+
+fn pty_size(rows: u16, cols: u16) -> PtySize {
+    PtySize {
+        rows,
+        cols,
+        pixel_width: 0,
+        pixel_height: 0,
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Substitute `${VAR}` references in plugin env values
+fn expand_env(value: &str) -> String {
+    let mut result = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        let name = &rest[start + 2..start + end];
+        result.push_str(&std::env::var(name).unwrap_or_default());
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
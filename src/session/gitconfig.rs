@@ -0,0 +1,171 @@
+// Per-session git configuration for commits agents make in their worktree
+
+use anyhow::Result;
+use std::path::Path;
+use super::Session;
+use crate::config::Config;
+use crate::git::hooks::{self, Hook};
+use crate::git::{self, isolation::BackendKind};
+
+/// Trailer recording which session produced a commit
+const TRAILER: &str = "Acta-Session";
+
+/// Scheme every push URL is rewritten to, which no git transport handles
+const NO_PUSH: &str = "acta-push-disabled://";
+
+/// Write the agent's identity and hooks into the session's worktree config.
+/// Unless the session allows pushing, pushes and updates to `protected`
+/// refs are refused and recorded in `log`.
+pub fn configure(
+    config: &Config,
+    session: &Session,
+    worktree: &Path,
+    protected: &[String],
+    log: &Path,
+) -> Result<()> {
+    let mut installed = Vec::new();
+
+    let identity = config
+        .get_plugin(&session.agent)
+        .and_then(|p| p.git_identity.as_ref());
+    if let Some(identity) = identity {
+        git::set_worktree_config(worktree, "user.name", &identity.name)?;
+        git::set_worktree_config(worktree, "user.email", &identity.email)?;
+
+        if identity.trailer {
+            let trailer = format!("{}: {}", TRAILER, session.id);
+            installed.push(Hook {
+                name: "prepare-commit-msg",
+                body: format!(
+                    "git interpret-trailers --in-place --if-exists addIfDifferent --trailer {} \"$1\"",
+                    hooks::quote(&trailer)
+                ),
+                reads_stdin: false,
+            });
+        }
+    }
+
+    // Copies have a private repository with nothing to push or protect
+    if session.backend == BackendKind::Worktree && !session.allow_push {
+        // The pre-push hook explains and logs the refusal, the rewrite keeps
+        // `git push --no-verify` from getting through
+        git::set_worktree_config(worktree, &format!("url.{}.pushInsteadOf", NO_PUSH), "")?;
+        installed.extend(guards(session, protected, log));
+    }
+
+    hooks::install(worktree, &installed)
+}
+
+fn guards(session: &Session, protected: &[String], log: &Path) -> Vec<Hook> {
+    let log = hooks::quote(&log.to_string_lossy());
+    let record = |what: &str| {
+        format!(
+            "echo \"$(date -u +%Y-%m-%dT%H:%M:%SZ) {}\" >> {}",
+            what, log
+        )
+    };
+
+    let name = hooks::quote(session.display_name());
+    let pre_push = format!(
+        "{}\nprintf 'acta: pushing is disabled in session %s (start it with --allow-push)\\n' {} >&2\nexit 1",
+        record("blocked: git push to $1 ($(printf '%s\\n' \"$input\" | awk '{print $3}' | paste -sd ' ' -))"),
+        name
+    );
+
+    // The session's own branch stays writable even if it matches, e.g. when
+    // started with `--branch main`
+    let own = session.branch.as_ref().map(|b| format!("refs/heads/{}", b));
+    let patterns: Vec<String> = protected
+        .iter()
+        .filter(|p| Some(*p) != own.as_ref())
+        .map(|p| hooks::quote(p).replace('*', "'*'"))
+        .collect();
+
+    let mut guards = vec![Hook {
+        name: "pre-push",
+        body: pre_push,
+        reads_stdin: true,
+    }];
+
+    if !patterns.is_empty() {
+        let body = format!(
+            r#"if [ "$1" = prepared ]; then
+  blocked=$(printf '%s\n' "$input" | while read -r old new ref; do
+    case "$ref" in
+      {}) echo "$ref" ;;
+    esac
+  done)
+  if [ -n "$blocked" ]; then
+    for ref in $blocked; do
+      {}
+      printf 'acta: %s is protected in session %s (start it with --allow-push)\n' "$ref" {} >&2
+    done
+    exit 1
+  fi
+fi"#,
+            patterns.join("|"),
+            record("blocked: update of $ref"),
+            name
+        );
+        guards.push(Hook {
+            name: "reference-transaction",
+            body,
+            reads_stdin: true,
+        });
+    }
+
+    guards
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::testing::Repo;
+    use crate::session::SessionStatus;
+    use std::time::SystemTime;
+
+    #[test]
+    fn skipping_the_hook_does_not_allow_pushing() {
+        let repo = Repo::new();
+        let remote = repo.path("remote.git");
+        git::run(&repo.dir, &["init", "-q", "--bare", &remote.to_string_lossy()]).unwrap();
+        repo.git(&["remote", "add", "origin", &remote.to_string_lossy()]);
+        let worktree = repo.worktree("session");
+
+        let session = Session {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: Some("session".to_string()),
+            agent: "claude".to_string(),
+            worktree_path: worktree.clone(),
+            status: SessionStatus::Running,
+            created_at: SystemTime::now(),
+            args: vec![],
+            repo_path: Some(repo.dir.clone()),
+            branch: Some("session".to_string()),
+            base_commit: None,
+            base_branch: None,
+            source_path: None,
+            owns_branch: true,
+            backend: BackendKind::Worktree,
+            allow_push: false,
+            sparse: vec![],
+            tags: vec![],
+            waiting_since: None,
+            status_before_sync: None,
+        };
+        let log = repo.path("session.log");
+        configure(&Config::default(), &session, &worktree, &[], &log).unwrap();
+
+        let pushes: [&[&str]; 2] = [
+            &["push", "-q", "origin", "session"],
+            &["push", "-q", "--no-verify", "origin", "session"],
+        ];
+        for args in pushes {
+            assert!(git::run(&worktree, args).is_err(), "{:?} went through", args);
+        }
+        assert!(git::run(&remote, &["rev-parse", "--verify", "-q", "refs/heads/session"]).is_err());
+
+        // The main worktree keeps pushing as before
+        repo.git(&["push", "-q", "origin", "master"]);
+    }
+}
@@ -1,10 +1,20 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
+use tracing::{info, warn};
 use uuid::Uuid;
+use crate::config::{Config, ProjectConfig};
+use crate::git;
+use crate::git::isolation::{self, BackendKind, Checkout, Isolation, WorkspaceSpec};
+
+pub mod agent;
+mod gitconfig;
+pub mod overlap;
+mod setup;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
@@ -15,23 +25,101 @@ pub struct Session {
     pub status: SessionStatus,
     pub created_at: SystemTime,
     pub args: Vec<String>,
+    /// Repository the worktree was created from (None for plain directories)
+    #[serde(default)]
+    pub repo_path: Option<PathBuf>,
+    #[serde(default)]
+    pub branch: Option<String>,
+    #[serde(default)]
+    pub base_commit: Option<String>,
+    /// Branch the session was started from, used by `acta sync`
+    #[serde(default)]
+    pub base_branch: Option<String>,
+    /// Original directory of a copy-isolated (non-git) session
+    #[serde(default)]
+    pub source_path: Option<PathBuf>,
+    /// Whether acta created `branch` and should delete it on cleanup
+    #[serde(default = "default_owns_branch")]
+    pub owns_branch: bool,
+    /// Isolation backend that owns `worktree_path`
+    #[serde(default)]
+    pub backend: BackendKind,
+    /// Started with `--allow-push`: no guard hooks in the worktree
+    #[serde(default)]
+    pub allow_push: bool,
+    /// Cone-mode sparse checkout paths; the whole tree when empty
+    #[serde(default)]
+    pub sparse: Vec<String>,
+    /// Free-form labels for finding the session again
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// When the agent started waiting for input, while it is
+    #[serde(default)]
+    pub waiting_since: Option<SystemTime>,
+    /// Status before a sync stopped on conflicts, restored once the sync is
+    /// continued or aborted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_before_sync: Option<SessionStatus>,
+}
+
+fn default_owns_branch() -> bool {
+    true
+}
+
+/// Options for `SessionManager::create_session`
+#[derive(Debug, Clone, Default)]
+pub struct NewSession {
+    pub agent: String,
+    pub name: Option<String>,
+    pub args: Vec<String>,
+    pub checkout: Checkout,
+    /// Isolation backend; picked from the project directory when None
+    pub backend: Option<BackendKind>,
+    pub allow_push: bool,
+    /// Directories to check out, on top of those from `sparse_profile`
+    pub sparse: Vec<String>,
+    pub sparse_profile: Option<String>,
+    pub tags: Vec<String>,
+    /// Id to give the session instead of a fresh one, for callers that
+    /// need to know it before creation finishes
+    pub id: Option<String>,
+}
+
+impl Session {
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.id[..8])
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum SessionStatus {
+    /// Worktree exists, setup commands are still running
+    Preparing,
     Running,
     Stopped,
     Failed,
+    /// A sync stopped on conflicts that need resolving in the worktree
+    Conflicted,
+    /// The agent is blocked on the user, e.g. a permission prompt
+    NeedsInput,
 }
 
 pub struct SessionManager {
     state_dir: PathBuf,
+    /// Where new sessions come from: the repository containing it, or the
+    /// directory itself when it is not under git
+    project_dir: PathBuf,
     sessions: HashMap<String, Session>,
 }
 
 impl SessionManager {
-    pub fn new() -> Result<Self> {
-        let state_dir = Self::get_state_dir()?;
+    /// Sessions in `~/.acta/sessions`, created from the current directory
+    pub fn open() -> Result<Self> {
+        let cwd = std::env::current_dir().context("Failed to read current directory")?;
+        Self::new(Self::get_state_dir()?, cwd)
+    }
+
+    pub fn new(state_dir: PathBuf, project_dir: PathBuf) -> Result<Self> {
         fs::create_dir_all(&state_dir)
             .context("Failed to create state directory")?;
 
@@ -39,10 +127,27 @@ impl SessionManager {
 
         Ok(Self {
             state_dir,
+            project_dir,
             sessions,
         })
     }
 
+    /// Directory holding session state; other processes change it too
+    pub fn state_dir(&self) -> &Path {
+        &self.state_dir
+    }
+
+    /// Directory new sessions are created from
+    pub fn project_dir(&self) -> &Path {
+        &self.project_dir
+    }
+
+    /// Re-read sessions saved by other acta processes
+    pub fn reload(&mut self) -> Result<()> {
+        self.sessions = Self::load_sessions(&self.state_dir)?;
+        Ok(())
+    }
+
     fn get_state_dir() -> Result<PathBuf> {
         let home = dirs::home_dir()
             .context("Could not determine home directory")?;
@@ -72,7 +177,11 @@ impl SessionManager {
 
     fn load_session(path: &Path) -> Result<Session> {
         let contents = fs::read_to_string(path)?;
-        let session: Session = serde_yaml::from_str(&contents)?;
+        let mut session: Session = serde_yaml::from_str(&contents)?;
+        // Sessions saved before backends were recorded
+        if session.source_path.is_some() {
+            session.backend = BackendKind::Copy;
+        }
         Ok(session)
     }
 
@@ -85,27 +194,263 @@ impl SessionManager {
 
     pub fn create_session(
         &mut self,
-        agent: String,
-        name: Option<String>,
-        args: Vec<String>,
+        config: &Config,
+        options: NewSession,
+        output: &mut dyn FnMut(&str),
     ) -> Result<Session> {
-        let id = Uuid::new_v4().to_string();
-        let worktree_path = PathBuf::from(format!(".acta/sessions/{}", id));
+        let NewSession {
+            agent,
+            name,
+            args,
+            checkout,
+            backend,
+            allow_push,
+            mut sparse,
+            sparse_profile,
+            tags,
+            id,
+        } = options;
+        let id = id.unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        let cwd = self.project_dir.clone();
+        let repo_path = git::repo_root(&cwd);
+        let project_dir = repo_path.clone().unwrap_or_else(|| cwd.clone());
+        let project = ProjectConfig::load(&project_dir)?;
+
+        if let Some(profile) = &sparse_profile {
+            let paths = config
+                .sparse_profile(&project, profile)
+                .with_context(|| format!("Unknown sparse profile '{}'", profile))?;
+            for path in paths.into_iter().rev() {
+                if !sparse.contains(&path) {
+                    sparse.insert(0, path);
+                }
+            }
+        }
+
+        let template = config.worktree_root(&project);
+        let worktree = worktree_location(template, &project_dir, &id, name.as_deref(), &agent)?;
+        if worktree.exists() {
+            bail!(
+                "Worktree path {} already exists; pick another name or add {{id}} to worktree.root",
+                worktree.display()
+            );
+        }
+        // Worktrees inside the project must not show up as untracked files
+        // or be copied into later sessions
+        let nested = worktree
+            .strip_prefix(&project_dir)
+            .ok()
+            .map(|relative| exclude_pattern(template, relative));
+        if let (Some(repo), Some(pattern)) = (&repo_path, &nested) {
+            git::exclude(repo, pattern)?;
+        }
+
+        let kind = backend.unwrap_or(if repo_path.is_some() {
+            BackendKind::Worktree
+        } else {
+            BackendKind::Copy
+        });
+        let source = match kind {
+            BackendKind::Worktree => repo_path.clone().context("Not inside a git repository")?,
+            _ => cwd.clone(),
+        };
+        if let (BackendKind::Worktree, Checkout::Branch(requested)) = (kind, &checkout) {
+            self.check_branch_free(&source, requested)?;
+        }
+        if kind == BackendKind::Copy {
+            info!("Not inside a git repository, copying {}", cwd.display());
+        }
+
+        let isolation = isolation::backend(kind, &self.state_dir);
+        let include = config.include_rules(&project);
+        let ignore = config.ignore_patterns(&project);
+        let workspace = isolation.create(&WorkspaceSpec {
+            id: &id,
+            source: &source,
+            path: &worktree,
+            checkout: &checkout,
+            include: &include,
+            ignore: &ignore,
+            nested: nested
+                .map(|p| project_dir.join(p.trim_matches('/')))
+                .as_deref(),
+            sparse: &sparse,
+            submodules: config.submodule_mode(&project),
+        })?;
+
+        let (repo_path, source_path) = match kind {
+            BackendKind::Copy => (None, Some(cwd)),
+            _ => (repo_path, None),
+        };
 
         let session = Session {
             id: id.clone(),
             name,
             agent,
-            worktree_path,
-            status: SessionStatus::Running,
+            worktree_path: worktree.clone(),
+            status: SessionStatus::Preparing,
             created_at: SystemTime::now(),
             args,
+            base_branch: match &checkout {
+                Checkout::Base(base) => Some(base.clone()),
+                _ => repo_path.as_deref().and_then(git::current_branch),
+            },
+            repo_path,
+            branch: workspace.branch,
+            base_commit: workspace.base_commit,
+            source_path,
+            owns_branch: workspace.owns_branch,
+            backend: isolation.kind(),
+            allow_push,
+            sparse,
+            tags,
+            waiting_since: None,
+            status_before_sync: None,
         };
 
+        let protected = config.protected_refs(&project);
+        if let Some(worktree) = isolation.git_dir(&session) {
+            let log = self.log_path(&id);
+            if let Err(e) = gitconfig::configure(config, &session, worktree, &protected, &log) {
+                let _ = isolation.remove(&session);
+                return Err(e);
+            }
+        }
+
         self.sessions.insert(id.clone(), session.clone());
         self.save_session(&session)?;
 
-        Ok(session)
+        let commands = config.setup_commands(&project);
+        let status = match setup::run(&worktree, &commands, &self.log_path(&id), output) {
+            Ok(()) => SessionStatus::Running,
+            Err(e) => {
+                warn!("Setup failed for session {}: {:#}", id, e);
+                SessionStatus::Failed
+            }
+        };
+        self.update_status(&id, status)?;
+
+        Ok(self.sessions[&id].clone())
+    }
+
+    /// Refuse to check out a branch another session already holds
+    fn check_branch_free(&self, repo: &Path, requested: &str) -> Result<()> {
+        let (local, _) = git::worktree::resolve_branch(repo, requested)?;
+        if let Some(holder) = self
+            .sessions
+            .values()
+            .find(|s| s.repo_path.as_deref() == Some(repo) && s.branch.as_deref() == Some(&local))
+        {
+            bail!(
+                "Branch '{}' is already checked out by session '{}' ({})",
+                local,
+                holder.display_name(),
+                &holder.id[..8]
+            );
+        }
+        Ok(())
+    }
+
+    /// Backend owning a session's workspace
+    pub fn isolation(&self, session: &Session) -> Box<dyn Isolation> {
+        isolation::backend(session.backend, &self.state_dir)
+    }
+
+    /// Worktree path, or an error if git commands cannot run in it
+    pub fn git_worktree<'a>(&self, session: &'a Session) -> Result<&'a Path> {
+        self.isolation(session).git_dir(session).with_context(|| {
+            format!("Session '{}' is not backed by a git worktree", session.display_name())
+        })
+    }
+
+    /// Queue a message for the agent; delivered by whichever acta process
+    /// hosts the live session
+    pub fn queue_message(&self, id: &str, message: &str) -> Result<()> {
+        let mut inbox = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.inbox_path(id))
+            .context("Failed to open session inbox")?;
+        writeln!(inbox, "{}", message.replace('\n', " "))?;
+        Ok(())
+    }
+
+    /// Remove and return the messages queued for a live session
+    pub fn take_messages(&self, id: &str) -> Result<Vec<String>> {
+        let path = self.inbox_path(id);
+        if !path.exists() {
+            return Ok(vec![]);
+        }
+        let contents = fs::read_to_string(&path).context("Failed to read session inbox")?;
+        fs::remove_file(&path)?;
+        Ok(contents.lines().map(str::to_string).collect())
+    }
+
+    /// Claim the session's agent for this process until the returned file
+    /// is dropped (or the process dies); fails while another process has it
+    pub fn host(&self, id: &str) -> Result<fs::File> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.host_path(id))
+            .context("Failed to open session host lock")?;
+        match file.try_lock() {
+            Ok(()) => Ok(file),
+            Err(fs::TryLockError::WouldBlock) => bail!("The agent is running in another acta process"),
+            Err(fs::TryLockError::Error(e)) => Err(e).context("Failed to lock session host lock"),
+        }
+    }
+
+    /// Whether an acta process runs the session's agent, and so delivers
+    /// queued messages to it
+    pub fn is_hosted(&self, id: &str) -> bool {
+        let Ok(file) = fs::File::open(self.host_path(id)) else {
+            return false;
+        };
+        matches!(file.try_lock_shared(), Err(fs::TryLockError::WouldBlock))
+    }
+
+    /// Withdraw queued messages the agent has not been sent yet
+    pub fn unqueue_messages(&self, id: &str, matches: impl Fn(&str) -> bool) -> Result<()> {
+        let path = self.inbox_path(id);
+        if !path.exists() {
+            return Ok(());
+        }
+        let contents = fs::read_to_string(&path).context("Failed to read session inbox")?;
+        let kept: Vec<&str> = contents.lines().filter(|m| !matches(m)).collect();
+        if kept.is_empty() {
+            fs::remove_file(&path)?;
+        } else {
+            fs::write(&path, kept.join("\n") + "\n").context("Failed to write session inbox")?;
+        }
+        Ok(())
+    }
+
+    fn inbox_path(&self, id: &str) -> PathBuf {
+        self.state_dir.join(format!("{}.inbox", id))
+    }
+
+    fn host_path(&self, id: &str) -> PathBuf {
+        self.state_dir.join(format!("{}.host", id))
+    }
+
+    /// When the session's state, log or inbox last changed on disk
+    pub fn last_activity(&self, id: &str) -> Option<SystemTime> {
+        [
+            self.state_dir.join(format!("{}.json", id)),
+            self.log_path(id),
+            self.inbox_path(id),
+        ]
+        .iter()
+        .filter_map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+        .max()
+    }
+
+    /// Event log for a session: setup output and blocked git operations
+    pub fn log_path(&self, id: &str) -> PathBuf {
+        self.state_dir.join(format!("{}.log", id))
     }
 
     pub fn get_session(&self, id_or_name: &str) -> Option<&Session> {
@@ -126,12 +471,18 @@ impl SessionManager {
         sessions
     }
 
-    pub fn kill_session(&mut self, id_or_name: &str) -> Result<()> {
+    pub fn kill_session(&mut self, id_or_name: &str, cleanup: bool) -> Result<()> {
         let session = self
             .get_session(id_or_name)
             .context("Session not found")?;
         let id = session.id.clone();
 
+        if cleanup {
+            if let Err(e) = self.isolation(session).remove(session) {
+                warn!("Failed to clean up workspace for session {}: {:#}", id, e);
+            }
+        }
+
         self.sessions.remove(&id);
 
         let path = self.state_dir.join(format!("{}.json", id));
@@ -139,15 +490,40 @@ impl SessionManager {
             fs::remove_file(path)?;
         }
 
+        let _ = fs::remove_file(self.log_path(&id));
+        let _ = fs::remove_file(self.inbox_path(&id));
+        let _ = fs::remove_file(self.host_path(&id));
+
         Ok(())
     }
 
+    /// Give a session a new name; names must stay unique
+    pub fn rename_session(&mut self, id_or_name: &str, name: &str) -> Result<Session> {
+        let id = self
+            .get_session(id_or_name)
+            .context("Session not found")?
+            .id
+            .clone();
+        if name.trim().is_empty() {
+            bail!("Session name cannot be empty");
+        }
+        if let Some(other) = self.get_session(name).filter(|s| s.id != id) {
+            bail!("Name '{}' is already used by session {}", name, &other.id[..8]);
+        }
+        self.update_session(&id, |s| s.name = Some(name.to_string()))
+    }
+
     pub fn update_status(&mut self, id: &str, status: SessionStatus) -> Result<()> {
         let session = self
             .sessions
             .get_mut(id)
             .context("Session not found")?;
 
+        session.waiting_since = match (&session.status, &status) {
+            (SessionStatus::NeedsInput, SessionStatus::NeedsInput) => session.waiting_since,
+            (_, SessionStatus::NeedsInput) => Some(SystemTime::now()),
+            _ => None,
+        };
         session.status = status.clone();
 
         // Clone the session to avoid borrow checker issues
@@ -156,10 +532,276 @@ impl SessionManager {
 
         Ok(())
     }
+
+    /// Apply `f` to a session and persist the result
+    pub fn update_session(&mut self, id: &str, f: impl FnOnce(&mut Session)) -> Result<Session> {
+        let session = self
+            .sessions
+            .get_mut(id)
+            .context("Session not found")?;
+
+        f(session);
+
+        let session_clone = session.clone();
+        self.save_session(&session_clone)?;
+
+        Ok(session_clone)
+    }
+}
+
+/// Expand a `worktree.root` template into an absolute path
+fn worktree_location(
+    template: &str,
+    project_dir: &Path,
+    id: &str,
+    name: Option<&str>,
+    agent: &str,
+) -> Result<PathBuf> {
+    if !["{id}", "{short_id}", "{name}"].iter().any(|p| template.contains(p)) {
+        bail!(
+            "worktree.root '{}' needs {{id}}, {{short_id}} or {{name}} to keep sessions apart",
+            template
+        );
+    }
+
+    let repo = project_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "root".to_string());
+    // Names may contain slashes; keep each session in a single directory
+    let name = name.unwrap_or(&id[..8]).replace(['/', '\\'], "-");
+
+    let expanded = template
+        .replace("{repo}", &repo)
+        .replace("{name}", &name)
+        .replace("{short_id}", &id[..8])
+        .replace("{id}", id)
+        .replace("{agent}", agent);
+
+    let path = match expanded.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .context("Could not determine home directory")?
+            .join(rest),
+        None => project_dir.join(expanded),
+    };
+    // Resolve `..` lexically so sibling roots like `../{repo}-worktrees`
+    // are not mistaken for paths inside the repository
+    let mut normalized = PathBuf::new();
+    for component in std::path::absolute(path)?.components() {
+        match component {
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            std::path::Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+    Ok(normalized)
+}
+
+/// Exclude entry for worktrees under the repository: the fixed directory
+/// prefix of the template, or the worktree itself when there is none
+fn exclude_pattern(template: &str, relative: &Path) -> String {
+    let fixed: Vec<&str> = template
+        .split('/')
+        .take_while(|c| !c.contains('{'))
+        .filter(|c| !c.is_empty() && *c != ".")
+        .collect();
+    let fixed = fixed.join("/");
+
+    if !fixed.is_empty() && !Path::new(&fixed).is_absolute() && relative.starts_with(&fixed) {
+        format!("/{}/", fixed)
+    } else {
+        format!("/{}/", relative.display())
+    }
 }
 
 impl Default for SessionManager {
     fn default() -> Self {
-        Self::new().expect("Failed to create SessionManager")
+        Self::open().expect("Failed to create SessionManager")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::memory;
+
+    /// Manager over a scratch state and project directory, removed on drop
+    struct Scratch {
+        root: PathBuf,
+        manager: SessionManager,
+    }
+
+    impl Scratch {
+        fn new() -> Self {
+            let root = std::env::temp_dir().join(format!("acta-test-{}", Uuid::new_v4()));
+            fs::create_dir_all(root.join("project")).unwrap();
+            let manager = SessionManager::new(root.join("state"), root.join("project")).unwrap();
+            Self { root, manager }
+        }
+
+        fn create(&mut self, name: Option<&str>) -> Session {
+            let options = NewSession {
+                agent: "claude".to_string(),
+                name: name.map(str::to_string),
+                backend: Some(BackendKind::Memory),
+                ..Default::default()
+            };
+            let mut config = Config::default();
+            config.worktree.root = Some(".worktrees/{id}".to_string());
+            self.manager.create_session(&config, options, &mut |_| {}).unwrap()
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    #[test]
+    fn create_saves_running_session() {
+        let mut scratch = Scratch::new();
+        let session = scratch.create(Some("first"));
+
+        assert_eq!(session.status, SessionStatus::Running);
+        assert_eq!(session.backend, BackendKind::Memory);
+        assert!(session.worktree_path.starts_with(scratch.root.join("project")));
+        assert!(scratch.manager.git_worktree(&session).is_err());
+        assert!(scratch.manager.state_dir().join(format!("{}.json", session.id)).exists());
+
+        let reopened = SessionManager::new(
+            scratch.manager.state_dir().to_path_buf(),
+            scratch.root.join("project"),
+        )
+        .unwrap();
+        assert_eq!(reopened.get_session("first").unwrap().id, session.id);
+    }
+
+    #[test]
+    fn list_is_ordered_by_creation() {
+        let mut scratch = Scratch::new();
+        let first = scratch.create(Some("first"));
+        let second = scratch.create(None);
+
+        let ids: Vec<&str> = scratch.manager.list_sessions().iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, [first.id.as_str(), second.id.as_str()]);
+    }
+
+    #[test]
+    fn status_reports_workspace_changes() {
+        let mut scratch = Scratch::new();
+        let session = scratch.create(None);
+        memory::write(&session.id, "src/lib.rs", "fn main() {}\n").unwrap();
+
+        let status = scratch.manager.isolation(&session).status(&session).unwrap();
+        assert!(status.exists);
+        assert_eq!(status.changed_files, 1);
+    }
+
+    #[test]
+    fn kill_removes_state_and_workspace() {
+        let mut scratch = Scratch::new();
+        let session = scratch.create(Some("doomed"));
+        scratch.manager.queue_message(&session.id, "hello").unwrap();
+
+        scratch.manager.kill_session("doomed", true).unwrap();
+
+        assert!(scratch.manager.get_session(&session.id).is_none());
+        assert!(scratch.manager.list_sessions().is_empty());
+        let state: Vec<_> = fs::read_dir(scratch.manager.state_dir()).unwrap().collect();
+        assert!(state.is_empty());
+        let status = scratch.manager.isolation(&session).status(&session).unwrap();
+        assert!(!status.exists);
+        assert!(scratch.manager.kill_session("doomed", true).is_err());
+    }
+
+    #[test]
+    fn unqueue_keeps_other_messages() {
+        let mut scratch = Scratch::new();
+        let session = scratch.create(None);
+        for message in ["keep me", "drop me", "keep me too"] {
+            scratch.manager.queue_message(&session.id, message).unwrap();
+        }
+
+        scratch.manager.unqueue_messages(&session.id, |m| m.starts_with("drop")).unwrap();
+        assert_eq!(scratch.manager.take_messages(&session.id).unwrap(), ["keep me", "keep me too"]);
+
+        scratch.manager.queue_message(&session.id, "drop me").unwrap();
+        scratch.manager.unqueue_messages(&session.id, |m| m.starts_with("drop")).unwrap();
+        assert!(scratch.manager.take_messages(&session.id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn hosting_lasts_as_long_as_the_claim() {
+        let mut scratch = Scratch::new();
+        let session = scratch.create(None);
+        assert!(!scratch.manager.is_hosted(&session.id));
+
+        let host = scratch.manager.host(&session.id).unwrap();
+        assert!(scratch.manager.is_hosted(&session.id));
+        let e = scratch.manager.host(&session.id).unwrap_err();
+        assert_eq!(e.to_string(), "The agent is running in another acta process");
+
+        drop(host);
+        assert!(!scratch.manager.is_hosted(&session.id));
+        let _host = scratch.manager.host(&session.id).unwrap();
+        scratch.manager.kill_session(&session.id, true).unwrap();
+        assert!(!scratch.manager.is_hosted(&session.id));
+    }
+
+    #[test]
+    fn rename_keeps_names_unique() {
+        let mut scratch = Scratch::new();
+        let first = scratch.create(Some("first"));
+        scratch.create(Some("second"));
+
+        let renamed = scratch.manager.rename_session("first", "renamed").unwrap();
+        assert_eq!(renamed.id, first.id);
+        assert_eq!(scratch.manager.get_session("renamed").unwrap().id, first.id);
+        assert!(scratch.manager.get_session("first").is_none());
+
+        assert!(scratch.manager.rename_session("renamed", "second").is_err());
+        assert!(scratch.manager.rename_session("renamed", "  ").is_err());
+        assert!(scratch.manager.rename_session("missing", "other").is_err());
+    }
+
+    const ID: &str = "0123abcd-0000-4000-8000-000000000000";
+
+    #[test]
+    fn worktree_location_expands_placeholders() {
+        let project = Path::new("/work/app");
+        let path = worktree_location(".acta/{repo}-{agent}/{name}/{short_id}/{id}", project, ID, Some("fix"), "claude");
+        assert_eq!(
+            path.unwrap(),
+            Path::new("/work/app/.acta/app-claude/fix/0123abcd").join(ID)
+        );
+    }
+
+    #[test]
+    fn worktree_location_names_default_and_stay_one_directory() {
+        let project = Path::new("/work/app");
+        let unnamed = worktree_location("wt/{name}", project, ID, None, "claude").unwrap();
+        assert_eq!(unnamed, Path::new("/work/app/wt/0123abcd"));
+
+        let slashed = worktree_location("wt/{name}", project, ID, Some("feat/a\\b"), "claude").unwrap();
+        assert_eq!(slashed, Path::new("/work/app/wt/feat-a-b"));
+    }
+
+    #[test]
+    fn worktree_location_resolves_parents_and_home() {
+        let project = Path::new("/work/app");
+        let sibling = worktree_location("../{repo}-worktrees/./{short_id}", project, ID, None, "claude").unwrap();
+        assert_eq!(sibling, Path::new("/work/app-worktrees/0123abcd"));
+
+        let home = worktree_location("~/.acta/{repo}/{name}", project, ID, Some("x"), "claude").unwrap();
+        assert_eq!(home, dirs::home_dir().unwrap().join(".acta/app/x"));
+    }
+
+    #[test]
+    fn worktree_location_needs_a_unique_placeholder() {
+        let project = Path::new("/work/app");
+        assert!(worktree_location("../{repo}-{agent}", project, ID, None, "claude").is_err());
     }
 }
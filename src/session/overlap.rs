@@ -0,0 +1,184 @@
+// Detect concurrent sessions editing the same files or hunks
+
+use std::collections::HashMap;
+use std::ops::Range;
+use tracing::debug;
+use super::{Session, SessionStatus};
+use crate::git::{checkpoint, diff};
+
+/// Two sessions touching the same file
+#[derive(Debug, Clone)]
+pub struct Overlap {
+    pub path: String,
+    pub first: String,
+    pub second: String,
+    /// Colliding line ranges (first, second) in base coordinates; empty when
+    /// only the file is shared (disjoint hunks, different bases or binaries)
+    pub hunks: Vec<(Range<usize>, Range<usize>)>,
+}
+
+struct Changes<'a> {
+    session: &'a Session,
+    files: HashMap<String, Vec<Range<usize>>>,
+}
+
+/// Find overlapping edits between running git-backed sessions
+pub fn detect(sessions: &[&Session]) -> Vec<Overlap> {
+    let changes: Vec<Changes> = sessions
+        .iter()
+        .filter(|s| matches!(s.status, SessionStatus::Running | SessionStatus::NeedsInput) && s.repo_path.is_some())
+        .filter_map(|session| {
+            let base = session.base_commit.as_deref()?;
+            let worktree = &session.worktree_path;
+            let files = checkpoint::snapshot_tree(worktree)
+                .and_then(|tree| diff::hunks(worktree, base, &tree));
+            match files {
+                Ok(files) => Some(Changes {
+                    session,
+                    files: files.into_iter().collect(),
+                }),
+                Err(e) => {
+                    debug!("Skipping session {} for overlap check: {:#}", session.id, e);
+                    None
+                }
+            }
+        })
+        .collect();
+
+    let mut overlaps = Vec::new();
+
+    for (i, a) in changes.iter().enumerate() {
+        for b in &changes[i + 1..] {
+            if a.session.repo_path != b.session.repo_path {
+                continue;
+            }
+            let same_base = a.session.base_commit == b.session.base_commit;
+
+            let mut paths: Vec<&String> = a.files.keys().filter(|p| b.files.contains_key(*p)).collect();
+            paths.sort();
+
+            for path in paths {
+                let (ra, rb) = (&a.files[path], &b.files[path]);
+                let hunks: Vec<_> = if same_base {
+                    ra.iter()
+                        .flat_map(|x| rb.iter().map(move |y| (x.clone(), y.clone())))
+                        .filter(|(x, y)| x.start < y.end && y.start < x.end)
+                        .collect()
+                } else {
+                    vec![]
+                };
+
+                overlaps.push(Overlap {
+                    path: path.clone(),
+                    first: a.session.id.clone(),
+                    second: b.session.id.clone(),
+                    hunks,
+                });
+            }
+        }
+    }
+
+    overlaps
+}
+
+impl Overlap {
+    /// True when the edited line ranges themselves collide
+    pub fn is_hunk_level(&self) -> bool {
+        !self.hunks.is_empty()
+    }
+
+    pub fn involves(&self, id: &str) -> bool {
+        self.first == id || self.second == id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use std::time::SystemTime;
+    use crate::git::isolation::BackendKind;
+    use crate::git::testing::{self, Repo};
+
+    fn session(repo: &Repo, worktree: &Path, base: &str) -> Session {
+        Session {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: None,
+            agent: "claude".to_string(),
+            worktree_path: worktree.to_path_buf(),
+            status: SessionStatus::Running,
+            created_at: SystemTime::now(),
+            args: vec![],
+            repo_path: Some(repo.dir.clone()),
+            branch: None,
+            base_commit: Some(base.to_string()),
+            base_branch: None,
+            source_path: None,
+            owns_branch: true,
+            backend: BackendKind::Worktree,
+            allow_push: false,
+            sparse: vec![],
+            tags: vec![],
+            waiting_since: None,
+            status_before_sync: None,
+        }
+    }
+
+    /// Two sessions on a base with a 20-line `a.txt`
+    fn setup() -> (Repo, Session, Session) {
+        let repo = Repo::new();
+        repo.write("a.txt", &(1..=20).map(|i| format!("line {}\n", i)).collect::<String>());
+        repo.commit("twenty lines");
+        let base = repo.git(&["rev-parse", "HEAD"]);
+        let (first, second) = (repo.worktree("first"), repo.worktree("second"));
+        let sessions = (session(&repo, &first, &base), session(&repo, &second, &base));
+        (repo, sessions.0, sessions.1)
+    }
+
+    fn edit(session: &Session, path: &str, from: &str, to: &str) {
+        let file = session.worktree_path.join(path);
+        let contents = std::fs::read_to_string(&file).unwrap_or_default();
+        testing::write(&session.worktree_path, path, &contents.replace(from, to));
+    }
+
+    #[test]
+    fn same_lines_overlap_at_hunk_level() {
+        let (_repo, a, b) = setup();
+        edit(&a, "a.txt", "line 5\n", "five\n");
+        edit(&b, "a.txt", "line 5\nline 6\n", "5 and 6\n");
+        edit(&b, "b.txt", "", "only in b\n");
+
+        let overlaps = detect(&[&a, &b]);
+        assert_eq!(overlaps.len(), 1);
+        let overlap = &overlaps[0];
+        assert_eq!(overlap.path, "a.txt");
+        assert!(overlap.involves(&a.id) && overlap.involves(&b.id));
+        assert_eq!(overlap.hunks, [(5..6, 5..7)]);
+    }
+
+    #[test]
+    fn disjoint_hunks_only_share_the_file() {
+        let (_repo, a, b) = setup();
+        edit(&a, "a.txt", "line 2\n", "two\n");
+        edit(&b, "a.txt", "line 18\n", "eighteen\n");
+
+        let overlaps = detect(&[&a, &b]);
+        assert_eq!(overlaps.len(), 1);
+        assert!(!overlaps[0].is_hunk_level());
+    }
+
+    #[test]
+    fn stopped_and_untouched_sessions_are_ignored() {
+        let (_repo, a, mut b) = setup();
+        edit(&a, "a.txt", "line 5\n", "five\n");
+        edit(&b, "a.txt", "line 5\n", "cinq\n");
+        b.status = SessionStatus::Stopped;
+        assert!(detect(&[&a, &b]).is_empty());
+
+        b.status = SessionStatus::NeedsInput;
+        assert_eq!(detect(&[&a, &b]).len(), 1);
+
+        edit(&b, "a.txt", "cinq\n", "line 5\n");
+        assert!(detect(&[&a, &b]).is_empty());
+    }
+}
@@ -0,0 +1,83 @@
+// Worktree setup commands (npm ci, cargo fetch, ...) run before the agent starts
+
+use anyhow::{bail, Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Run each command in `worktree` in order, sending every output line to
+/// `output` and appending it to `log_path`. Stops at the first failure.
+pub fn run(
+    worktree: &Path,
+    commands: &[String],
+    log_path: &Path,
+    output: &mut dyn FnMut(&str),
+) -> Result<()> {
+    if commands.is_empty() {
+        return Ok(());
+    }
+
+    let log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)
+        .with_context(|| format!("Failed to open log {}", log_path.display()))?;
+    let log = Arc::new(Mutex::new(log));
+
+    for command in commands {
+        let header = format!("$ {}", command);
+        writeln!(log.lock().unwrap(), "{}", header)?;
+        output(&header);
+
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .current_dir(worktree)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run setup command '{}'", command))?;
+
+        let (tx, rx) = std::sync::mpsc::channel::<String>();
+        let readers = [
+            forward(child.stdout.take(), tx.clone(), log.clone()),
+            forward(child.stderr.take(), tx, log.clone()),
+        ];
+
+        for line in rx {
+            output(&line);
+        }
+        for reader in readers {
+            let _ = reader.join();
+        }
+
+        let status = child.wait()?;
+        if !status.success() {
+            let message = format!("Setup command '{}' failed ({})", command, status);
+            writeln!(log.lock().unwrap(), "{}", message)?;
+            bail!(message);
+        }
+    }
+
+    Ok(())
+}
+
+fn forward(
+    stream: Option<impl Read + Send + 'static>,
+    tx: std::sync::mpsc::Sender<String>,
+    log: Arc<Mutex<File>>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let Some(stream) = stream else { return };
+        for line in BufReader::new(stream).lines().map_while(Result::ok) {
+            if let Ok(mut log) = log.lock() {
+                let _ = writeln!(log, "{}", line);
+            }
+            let _ = tx.send(line);
+        }
+    })
+}
@@ -0,0 +1,621 @@
+// Copy mode: a snapshot of a pane's scrollback and screen to move through
+// with vi keys, search with `/` and `?`, and select text from. Like the
+// dialogs it only collects input; the App copies or saves the selection.
+
+use base64::Engine;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Style;
+use std::cell::Cell;
+use super::dialog::Input;
+use super::pane;
+use crate::config::theme::Theme;
+
+/// Copy mode keys, for the help footer
+pub const HELP: &str = "h/j/k/l w/b/e 0/^/$ g/G: move | C-u/C-d: half page | v/V: select | / ?: search | n/N: next/prev match | y/Enter: copy | s: save to file | q: exit";
+
+struct Line {
+    /// Text and style of each column; empty for the right half of wide
+    /// characters
+    cells: Vec<(String, Style)>,
+    /// The program's text continues on the next line
+    wrapped: bool,
+}
+
+impl Line {
+    /// Columns up to the last non-blank one
+    fn len(&self) -> usize {
+        self.cells
+            .iter()
+            .rposition(|(text, _)| !text.trim().is_empty())
+            .map_or(0, |i| i + 1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Class {
+    Blank,
+    Word,
+    Punctuation,
+}
+
+struct Selection {
+    row: usize,
+    col: usize,
+    /// Whole lines, like vi's `V`
+    lines: bool,
+}
+
+struct Search {
+    pattern: String,
+    /// Lowercase pattern matched against lowercased text, when it has no
+    /// uppercase letters
+    needle: String,
+    fold: bool,
+    forward: bool,
+}
+
+impl Search {
+    fn new(pattern: &str, forward: bool) -> Self {
+        let fold = !pattern.chars().any(char::is_uppercase);
+        Self {
+            pattern: pattern.to_string(),
+            needle: if fold { pattern.to_lowercase() } else { pattern.to_string() },
+            fold,
+            forward,
+        }
+    }
+}
+
+enum Prompt {
+    Search { forward: bool },
+    Save,
+}
+
+/// What the App should do after a key press in copy mode
+pub enum Outcome {
+    Open,
+    Exit,
+    /// Stay in copy mode and show this in the footer
+    Message(String),
+    Copy(String),
+    Save { path: String, text: String },
+}
+
+pub struct CopyMode {
+    session: String,
+    lines: Vec<Line>,
+    row: usize,
+    col: usize,
+    /// First line on screen
+    top: usize,
+    selection: Option<Selection>,
+    search: Option<Search>,
+    prompt: Option<(Prompt, Input)>,
+    /// Where `s` offers to save the selection
+    save_path: String,
+    /// Rows of the pane at the last draw, for paging
+    page: Cell<usize>,
+}
+
+impl CopyMode {
+    /// Snapshot the scrollback and screen of `parser`, with the cursor
+    /// where the program's cursor is
+    pub fn new(session: &str, parser: &mut vt100::Parser, save_path: &str) -> Self {
+        let screen = parser.screen_mut();
+        let (rows, cols) = screen.size();
+        let (cursor_row, cursor_col) = screen.cursor_position();
+        screen.set_scrollback(usize::MAX);
+        let history = screen.scrollback();
+
+        // Page from the oldest line of history down to the screen
+        let mut lines = Vec::with_capacity(history + rows as usize);
+        let mut offset = history;
+        loop {
+            screen.set_scrollback(offset);
+            let count = match offset {
+                0 => rows as usize,
+                _ => offset.min(rows as usize),
+            };
+            for row in 0..count as u16 {
+                let cells = (0..cols)
+                    .map(|col| match screen.cell(row, col) {
+                        Some(cell) if cell.is_wide_continuation() => (String::new(), Style::default()),
+                        Some(cell) if cell.has_contents() => (cell.contents().to_string(), pane::cell_style(cell)),
+                        Some(cell) => (" ".to_string(), pane::cell_style(cell)),
+                        None => (" ".to_string(), Style::default()),
+                    })
+                    .collect();
+                lines.push(Line {
+                    cells,
+                    wrapped: screen.row_wrapped(row),
+                });
+            }
+            if offset == 0 {
+                break;
+            }
+            offset -= count;
+        }
+        screen.set_scrollback(0);
+
+        let row = history + cursor_row as usize;
+        while lines.len() > row + 1 && lines.last().is_some_and(|l| l.len() == 0) {
+            lines.pop();
+        }
+        Self {
+            session: session.to_string(),
+            lines,
+            row,
+            col: cursor_col as usize,
+            top: history,
+            selection: None,
+            search: None,
+            prompt: None,
+            save_path: save_path.to_string(),
+            page: Cell::new(rows.max(1) as usize),
+        }
+    }
+
+    /// Session the snapshot was taken from
+    pub fn session(&self) -> &str {
+        &self.session
+    }
+
+    /// Position, selection and search, for the pane's footer
+    pub fn status(&self) -> String {
+        let mut status = format!("Copy mode [{}/{}]", self.row + 1, self.lines.len());
+        if let Some(((start, _), (end, _))) = self.bounds() {
+            status.push_str(&format!(" · {} line(s) selected", end - start + 1));
+        }
+        if let Some(search) = &self.search {
+            let prefix = if search.forward { '/' } else { '?' };
+            status.push_str(&format!(" · {}{}", prefix, search.pattern));
+        }
+        status
+    }
+
+    /// Search or save prompt being typed, with its title
+    pub fn prompt(&self) -> Option<(&'static str, String)> {
+        let (prompt, input) = self.prompt.as_ref()?;
+        Some(match prompt {
+            Prompt::Search { forward: true } => ("Search down (Enter: find | Esc: cancel)", format!("/{}", input.text())),
+            Prompt::Search { forward: false } => ("Search up (Enter: find | Esc: cancel)", format!("?{}", input.text())),
+            Prompt::Save => ("Save selection (Enter: save | Esc: cancel)", format!("Save to: {}", input.text())),
+        })
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Outcome {
+        if self.prompt.is_some() {
+            return self.handle_prompt(key);
+        }
+
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let half = (self.page.get() / 2).max(1) as isize;
+        let page = self.page.get().max(1) as isize;
+        match key.code {
+            KeyCode::Char('u') if ctrl => self.scroll(-half),
+            KeyCode::Char('d') if ctrl => self.scroll(half),
+            KeyCode::Char('b') if ctrl => self.scroll(-page),
+            KeyCode::Char('f') if ctrl => self.scroll(page),
+            KeyCode::PageUp => self.scroll(-page),
+            KeyCode::PageDown => self.scroll(page),
+            _ if ctrl => {}
+            KeyCode::Esc if self.selection.is_some() => self.selection = None,
+            KeyCode::Esc | KeyCode::Char('q') => return Outcome::Exit,
+            KeyCode::Char('h') | KeyCode::Left => self.col = self.col.saturating_sub(1),
+            KeyCode::Char('l') | KeyCode::Right => self.col = (self.col + 1).min(self.width(self.row) - 1),
+            KeyCode::Char('j') | KeyCode::Down => self.move_to(self.row + 1, self.col),
+            KeyCode::Char('k') | KeyCode::Up => self.move_to(self.row.saturating_sub(1), self.col),
+            KeyCode::Char('w') => self.word_forward(),
+            KeyCode::Char('b') => self.word_backward(),
+            KeyCode::Char('e') => self.word_end(),
+            KeyCode::Char('0') | KeyCode::Home => self.col = 0,
+            KeyCode::Char('^') => {
+                let line = &self.lines[self.row];
+                self.col = line.cells.iter().position(|(t, _)| !t.trim().is_empty()).unwrap_or(0);
+            }
+            KeyCode::Char('$') | KeyCode::End => self.col = self.lines[self.row].len().saturating_sub(1),
+            KeyCode::Char('g') => self.move_to(0, 0),
+            KeyCode::Char('G') => self.move_to(self.lines.len(), 0),
+            KeyCode::Char('H') => self.move_to(self.top, self.col),
+            KeyCode::Char('M') => self.move_to(self.top + self.page.get() / 2, self.col),
+            KeyCode::Char('L') => self.move_to(self.top + self.page.get() - 1, self.col),
+            KeyCode::Char('v') => self.toggle_selection(false),
+            KeyCode::Char('V') => self.toggle_selection(true),
+            KeyCode::Char('/') => self.prompt = Some((Prompt::Search { forward: true }, Input::default())),
+            KeyCode::Char('?') => self.prompt = Some((Prompt::Search { forward: false }, Input::default())),
+            KeyCode::Char('n') => return self.repeat_search(false),
+            KeyCode::Char('N') => return self.repeat_search(true),
+            KeyCode::Char('y') | KeyCode::Enter => return Outcome::Copy(self.selected_text()),
+            KeyCode::Char('s') => self.prompt = Some((Prompt::Save, Input::new(&self.save_path))),
+            _ => {}
+        }
+        self.follow();
+        Outcome::Open
+    }
+
+    fn handle_prompt(&mut self, key: KeyEvent) -> Outcome {
+        let Some((prompt, input)) = &mut self.prompt else {
+            return Outcome::Open;
+        };
+        match key.code {
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Enter => {
+                let text = input.text().to_string();
+                match *prompt {
+                    Prompt::Search { forward } => {
+                        self.prompt = None;
+                        // An empty pattern repeats the last search in the new direction
+                        match (text.is_empty(), &mut self.search) {
+                            (true, Some(search)) => search.forward = forward,
+                            (true, None) => return Outcome::Open,
+                            (false, _) => self.search = Some(Search::new(&text, forward)),
+                        }
+                        return self.repeat_search(false);
+                    }
+                    Prompt::Save => {
+                        let path = text.trim();
+                        if path.is_empty() {
+                            return Outcome::Message("Enter a file to save to".to_string());
+                        }
+                        return Outcome::Save {
+                            path: path.to_string(),
+                            text: self.selected_text(),
+                        };
+                    }
+                }
+            }
+            _ => input.handle_key(key),
+        }
+        Outcome::Open
+    }
+
+    /// Columns the cursor can be on in `row`
+    fn width(&self, row: usize) -> usize {
+        self.lines.get(row).map_or(0, |l| l.cells.len()).max(1)
+    }
+
+    fn move_to(&mut self, row: usize, col: usize) {
+        self.row = row.min(self.lines.len().saturating_sub(1));
+        self.col = col.min(self.width(self.row) - 1);
+    }
+
+    /// Move the view and the cursor by `lines`, down when positive
+    fn scroll(&mut self, lines: isize) {
+        let last = self.lines.len().saturating_sub(1);
+        self.top = self.top.saturating_add_signed(lines).min(last);
+        self.move_to(self.row.saturating_add_signed(lines), self.col);
+    }
+
+    /// Scroll the view to keep the cursor on screen
+    fn follow(&mut self) {
+        self.top = self.top_for(self.page.get());
+    }
+
+    fn top_for(&self, height: usize) -> usize {
+        let top = self.top.min(self.row);
+        if self.row >= top + height.max(1) {
+            self.row + 1 - height.max(1)
+        } else {
+            top
+        }
+    }
+
+    fn class(&self, row: usize, col: usize) -> Class {
+        match self.lines[row].cells.get(col).map(|(t, _)| t.as_str()) {
+            None => Class::Blank,
+            Some(text) if text.trim().is_empty() && !text.is_empty() => Class::Blank,
+            Some(text) if text.is_empty() || text.chars().all(|c| c.is_alphanumeric() || c == '_') => Class::Word,
+            Some(_) => Class::Punctuation,
+        }
+    }
+
+    /// The position after (or before) `row`, `col`, running on into the
+    /// next (or previous) line
+    fn step(&self, row: usize, col: usize, forward: bool) -> Option<(usize, usize)> {
+        if forward {
+            if col + 1 < self.width(row) {
+                Some((row, col + 1))
+            } else {
+                (row + 1 < self.lines.len()).then_some((row + 1, 0))
+            }
+        } else if col > 0 {
+            Some((row, col - 1))
+        } else {
+            row.checked_sub(1).map(|row| (row, self.width(row) - 1))
+        }
+    }
+
+    /// Start of the next word; line ends separate words
+    fn word_forward(&mut self) {
+        let (mut row, mut col) = (self.row, self.col);
+        let start = self.class(row, col);
+        let mut gap = start == Class::Blank;
+        while let Some((r, c)) = self.step(row, col, true) {
+            gap |= r != row;
+            (row, col) = (r, c);
+            match self.class(row, col) {
+                Class::Blank => gap = true,
+                class if gap || class != start => return self.move_to(row, col),
+                _ => {}
+            }
+        }
+    }
+
+    /// Start of the word before the cursor
+    fn word_backward(&mut self) {
+        let (mut row, mut col) = (self.row, self.col);
+        while let Some((r, c)) = self.step(row, col, false) {
+            (row, col) = (r, c);
+            if self.class(row, col) != Class::Blank {
+                let class = self.class(row, col);
+                while col > 0 && self.class(row, col - 1) == class {
+                    col -= 1;
+                }
+                return self.move_to(row, col);
+            }
+        }
+    }
+
+    /// End of the word under or after the cursor
+    fn word_end(&mut self) {
+        let (mut row, mut col) = (self.row, self.col);
+        while let Some((r, c)) = self.step(row, col, true) {
+            (row, col) = (r, c);
+            if self.class(row, col) != Class::Blank {
+                let class = self.class(row, col);
+                while col + 1 < self.width(row) && self.class(row, col + 1) == class {
+                    col += 1;
+                }
+                return self.move_to(row, col);
+            }
+        }
+    }
+
+    fn toggle_selection(&mut self, lines: bool) {
+        self.selection = match &self.selection {
+            Some(selection) if selection.lines == lines => None,
+            Some(selection) => Some(Selection { lines, ..*selection }),
+            None => Some(Selection {
+                row: self.row,
+                col: self.col,
+                lines,
+            }),
+        };
+    }
+
+    /// First and last selected positions, both included
+    fn bounds(&self) -> Option<((usize, usize), (usize, usize))> {
+        let selection = self.selection.as_ref()?;
+        let (start, end) = if (selection.row, selection.col) <= (self.row, self.col) {
+            ((selection.row, selection.col), (self.row, self.col))
+        } else {
+            ((self.row, self.col), (selection.row, selection.col))
+        };
+        Some(match selection.lines {
+            true => ((start.0, 0), (end.0, usize::MAX)),
+            false => (start, end),
+        })
+    }
+
+    fn is_selected(&self, row: usize, col: usize) -> bool {
+        self.bounds().is_some_and(|(start, end)| (row, col) >= start && (row, col) <= end)
+    }
+
+    /// The selection as text, or the cursor line without one. Trailing
+    /// blanks are dropped and wrapped lines joined back together.
+    fn selected_text(&self) -> String {
+        let (start, end) = self.bounds().unwrap_or(((self.row, 0), (self.row, usize::MAX)));
+        let mut text = String::new();
+        for row in start.0..=end.0 {
+            let line = &self.lines[row];
+            let from = if row == start.0 { start.1 } else { 0 };
+            let to = if row == end.0 { end.1.saturating_add(1) } else { usize::MAX }.min(line.cells.len());
+            let part: String = line.cells[from.min(to)..to].iter().map(|(t, _)| t.as_str()).collect();
+            if line.wrapped && row < end.0 {
+                text.push_str(&part);
+            } else {
+                text.push_str(part.trim_end());
+                if row < end.0 {
+                    text.push('\n');
+                }
+            }
+        }
+        text
+    }
+
+    /// Columns where `search` matches in `row`, end excluded
+    fn matches(&self, row: usize, search: &Search) -> Vec<(usize, usize)> {
+        let line = &self.lines[row];
+        let mut text = String::new();
+        let mut starts = Vec::with_capacity(line.cells.len() + 1);
+        for (cell, _) in &line.cells {
+            starts.push(text.len());
+            match search.fold {
+                true => text.push_str(&cell.to_lowercase()),
+                false => text.push_str(cell),
+            }
+        }
+        starts.push(text.len());
+
+        // The cell an offset falls in; the right halves of wide characters
+        // start where the next cell does, so the last cell starting there
+        let column = |offset: usize| starts.iter().rposition(|&o| o <= offset).unwrap_or(0);
+        text.match_indices(&search.needle)
+            .map(|(offset, found)| (column(offset), column(offset + found.len())))
+            .collect()
+    }
+
+    /// Move to the next match of the last search, in its direction or
+    /// against it with `reverse`, wrapping around at either end
+    fn repeat_search(&mut self, reverse: bool) -> Outcome {
+        let Some(search) = &self.search else {
+            return Outcome::Message("No previous search".to_string());
+        };
+        let forward = search.forward != reverse;
+        let count = self.lines.len();
+
+        // The cursor line is looked at twice: after the cursor first, and
+        // before it once the search has wrapped around
+        for step in 0..=count {
+            let row = match forward {
+                true => (self.row + step) % count,
+                false => (self.row + count - step % count) % count,
+            };
+            let mut columns = self.matches(row, search).into_iter().map(|(start, _)| start);
+            let found = match (forward, step) {
+                (true, 0) => columns.find(|&c| c > self.col),
+                (true, _) => columns.find(|&c| step < count || c <= self.col),
+                (false, 0) => columns.rfind(|&c| c < self.col),
+                (false, _) => columns.rfind(|&c| step < count || c >= self.col),
+            };
+            if let Some(col) = found {
+                self.move_to(row, col);
+                self.follow();
+                return Outcome::Open;
+            }
+        }
+        Outcome::Message(format!("Pattern not found: {}", search.pattern))
+    }
+
+    /// Draw the snapshot into `area`; returns where the cursor is
+    pub fn render(&self, area: Rect, buf: &mut Buffer, theme: &Theme) -> Option<(u16, u16)> {
+        let height = area.height as usize;
+        self.page.set(height.max(1));
+        let top = self.top_for(height);
+        // Replace the program's colors and attributes rather than mixing with them
+        let selected = Style::reset().patch(theme.copy_selection);
+        let matched = Style::reset().patch(theme.copy_match);
+
+        for (y, row) in (top..self.lines.len()).take(height).enumerate() {
+            let matches = match &self.search {
+                Some(search) => self.matches(row, search),
+                None => vec![],
+            };
+            for (x, (text, style)) in self.lines[row].cells.iter().enumerate().take(area.width as usize) {
+                if text.is_empty() {
+                    continue;
+                }
+                let Some(target) = buf.cell_mut((area.x + x as u16, area.y + y as u16)) else {
+                    continue;
+                };
+                let style = if self.is_selected(row, x) {
+                    selected
+                } else if matches.iter().any(|&(start, end)| (start..end).contains(&x)) {
+                    matched
+                } else {
+                    *style
+                };
+                target.set_symbol(text);
+                target.set_style(style);
+            }
+        }
+
+        let (y, x) = (self.row.checked_sub(top)?, self.col);
+        (y < height && x < area.width as usize).then(|| (area.x + x as u16, area.y + y as u16))
+    }
+}
+
+/// Escape sequence that asks the terminal to put `text` on the clipboard
+pub fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64::engine::general_purpose::STANDARD.encode(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Copy mode over a terminal of `rows` x `cols` that printed `text`
+    fn snapshot(rows: u16, cols: u16, text: &str) -> CopyMode {
+        let mut parser = vt100::Parser::new(rows, cols, 100);
+        parser.process(text.replace('\n', "\r\n").as_bytes());
+        CopyMode::new("session", &mut parser, "copy.txt")
+    }
+
+    fn press(copy: &mut CopyMode, keys: &str) -> Outcome {
+        let mut outcome = Outcome::Open;
+        for c in keys.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                '\x1b' => KeyCode::Esc,
+                c => KeyCode::Char(c),
+            };
+            outcome = copy.handle_key(KeyEvent::from(code));
+        }
+        outcome
+    }
+
+    #[test]
+    fn matches_fold_case_unless_uppercase_and_count_columns() {
+        let copy = snapshot(5, 20, "Foo foo 日本foo");
+        let columns = |pattern| copy.matches(0, &Search::new(pattern, true));
+
+        assert_eq!(columns("foo"), [(0, 3), (4, 7), (12, 15)]);
+        assert_eq!(columns("Foo"), [(0, 3)]);
+        assert_eq!(columns("本f"), [(10, 13)]);
+        assert_eq!(columns("bar"), []);
+    }
+
+    #[test]
+    fn searches_wrap_around_both_ways() {
+        let mut copy = snapshot(5, 20, "a x\nb x\nc x");
+        assert_eq!((copy.row, copy.col), (2, 3));
+        let mut at = |keys| {
+            press(&mut copy, keys);
+            (copy.row, copy.col)
+        };
+
+        assert_eq!(at("/x\n"), (0, 2));
+        assert_eq!(at("n"), (1, 2));
+        assert_eq!(at("n"), (2, 2));
+        assert_eq!(at("n"), (0, 2));
+        assert_eq!(at("N"), (2, 2));
+        // An empty pattern searches again the other way
+        assert_eq!(at("?\n"), (1, 2));
+        assert_eq!(at("n"), (0, 2));
+    }
+
+    #[test]
+    fn a_lone_match_is_found_again() {
+        let mut copy = snapshot(5, 20, "only x here");
+        copy.move_to(0, 5);
+        copy.search = Some(Search::new("x", true));
+        assert!(matches!(copy.repeat_search(false), Outcome::Open));
+        assert_eq!((copy.row, copy.col), (0, 5));
+        assert!(matches!(copy.repeat_search(true), Outcome::Open));
+        assert_eq!((copy.row, copy.col), (0, 5));
+
+        let outcome = press(&mut copy, "/zzz\n");
+        assert!(matches!(outcome, Outcome::Message(m) if m == "Pattern not found: zzz"));
+        let mut fresh = snapshot(5, 20, "text");
+        assert!(matches!(fresh.repeat_search(false), Outcome::Message(m) if m == "No previous search"));
+    }
+
+    #[test]
+    fn selected_text_joins_wrapped_lines() {
+        // The first line wraps at 10 columns, and scrolls into history
+        let mut copy = snapshot(3, 10, "0123456789abc\nsecond   \nthird\nfourth");
+        assert_eq!(copy.lines.len(), 5);
+
+        // Without a selection, the cursor line
+        copy.move_to(2, 3);
+        assert_eq!(copy.selected_text(), "second");
+
+        press(&mut copy, "gv");
+        copy.move_to(2, 2);
+        assert_eq!(copy.selected_text(), "0123456789abc\nsec");
+
+        press(&mut copy, "\x1b");
+        copy.move_to(0, 4);
+        press(&mut copy, "v");
+        copy.move_to(0, 6);
+        assert_eq!(copy.selected_text(), "456");
+
+        press(&mut copy, "\x1b");
+        copy.move_to(3, 1);
+        press(&mut copy, "V");
+        copy.move_to(2, 5);
+        assert_eq!(copy.selected_text(), "second\nthird");
+    }
+}
@@ -0,0 +1,325 @@
+// Modal dialogs drawn over the TUI: the new-session form, the rename prompt
+// and the kill and quit confirmations. They only collect input; the App runs
+// the resulting SessionManager operation.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+use crate::config::theme::Theme;
+use crate::git::isolation::Checkout;
+use crate::session::NewSession;
+
+/// Single-line text entry
+#[derive(Debug, Default)]
+pub struct Input {
+    value: String,
+}
+
+impl Input {
+    pub fn new(value: &str) -> Self {
+        Self {
+            value: value.to_string(),
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        self.value.trim()
+    }
+
+    /// The text as typed, including surrounding spaces
+    pub fn text(&self) -> &str {
+        &self.value
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => self.value.clear(),
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => self.value.push(c),
+            KeyCode::Backspace => {
+                self.value.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+pub struct NewSessionForm {
+    agents: Vec<String>,
+    agent: usize,
+    name: Input,
+    /// Branch to start from; the current branch when empty
+    base: Input,
+    /// Extra agent arguments, split on whitespace
+    args: Input,
+    current_branch: Option<String>,
+    field: usize,
+}
+
+impl NewSessionForm {
+    const FIELDS: usize = 4;
+
+    /// `agent` is preselected when it is one of `agents`
+    pub fn new(agents: Vec<String>, agent: Option<&str>, current_branch: Option<String>) -> Self {
+        let selected = agent
+            .and_then(|a| agents.iter().position(|name| name == a))
+            .unwrap_or(0);
+        Self {
+            agents,
+            agent: selected,
+            name: Input::default(),
+            base: Input::default(),
+            args: Input::default(),
+            current_branch,
+            field: 0,
+        }
+    }
+
+    fn options(&self) -> Option<NewSession> {
+        let agent = self.agents.get(self.agent)?.clone();
+        let name = Some(self.name.value().to_string()).filter(|n| !n.is_empty());
+        let checkout = match self.base.value() {
+            "" => Checkout::NewBranch,
+            base => Checkout::Base(base.to_string()),
+        };
+        let args = self.args.value().split_whitespace().map(str::to_string).collect();
+        Some(NewSession {
+            agent,
+            name,
+            args,
+            checkout,
+            ..Default::default()
+        })
+    }
+}
+
+pub enum Dialog {
+    New {
+        form: NewSessionForm,
+        error: Option<String>,
+    },
+    Rename {
+        id: String,
+        input: Input,
+        error: Option<String>,
+    },
+    Kill {
+        id: String,
+        name: String,
+        error: Option<String>,
+    },
+    /// Quitting stops the agents the TUI runs
+    Quit {
+        running: usize,
+    },
+}
+
+/// What the App should do after a key press in a dialog
+pub enum Outcome {
+    Open,
+    Cancel,
+    Create(NewSession),
+    Rename { id: String, name: String },
+    Kill { id: String, cleanup: bool },
+    Quit,
+}
+
+impl Dialog {
+    pub fn handle_key(&mut self, key: KeyEvent) -> Outcome {
+        if key.code == KeyCode::Esc {
+            return Outcome::Cancel;
+        }
+
+        match self {
+            Dialog::New { form, error } => match key.code {
+                KeyCode::Enter => match form.options() {
+                    Some(options) => return Outcome::Create(options),
+                    None => *error = Some("No agents registered; see acta plugin register".to_string()),
+                },
+                KeyCode::Tab | KeyCode::Down => form.field = (form.field + 1) % NewSessionForm::FIELDS,
+                KeyCode::BackTab | KeyCode::Up => {
+                    form.field = (form.field + NewSessionForm::FIELDS - 1) % NewSessionForm::FIELDS
+                }
+                KeyCode::Left | KeyCode::Right | KeyCode::Char(' ') if form.field == 0 => {
+                    let count = form.agents.len().max(1);
+                    form.agent = match key.code {
+                        KeyCode::Left => (form.agent + count - 1) % count,
+                        _ => (form.agent + 1) % count,
+                    };
+                }
+                _ => match form.field {
+                    1 => form.name.handle_key(key),
+                    2 => form.base.handle_key(key),
+                    3 => form.args.handle_key(key),
+                    _ => {}
+                },
+            },
+            Dialog::Rename { id, input, .. } => match key.code {
+                KeyCode::Enter => {
+                    return Outcome::Rename {
+                        id: id.clone(),
+                        name: input.value().to_string(),
+                    }
+                }
+                _ => input.handle_key(key),
+            },
+            Dialog::Kill { id, .. } => match key.code {
+                KeyCode::Char('y') => {
+                    return Outcome::Kill {
+                        id: id.clone(),
+                        cleanup: true,
+                    }
+                }
+                KeyCode::Char('k') => {
+                    return Outcome::Kill {
+                        id: id.clone(),
+                        cleanup: false,
+                    }
+                }
+                KeyCode::Char('n') => return Outcome::Cancel,
+                _ => {}
+            },
+            Dialog::Quit { .. } => match key.code {
+                KeyCode::Char('y') => return Outcome::Quit,
+                KeyCode::Char('n') => return Outcome::Cancel,
+                _ => {}
+            },
+        }
+        Outcome::Open
+    }
+
+    /// Session the dialog acts on
+    pub fn session(&self) -> Option<&str> {
+        match self {
+            Dialog::New { .. } | Dialog::Quit { .. } => None,
+            Dialog::Rename { id, .. } | Dialog::Kill { id, .. } => Some(id),
+        }
+    }
+
+    /// Show why the last attempt failed and keep the dialog open
+    pub fn set_error(&mut self, message: String) {
+        match self {
+            Dialog::New { error, .. } | Dialog::Rename { error, .. } | Dialog::Kill { error, .. } => {
+                *error = Some(message)
+            }
+            Dialog::Quit { .. } => {}
+        }
+    }
+
+    pub fn render(&self, f: &mut Frame, theme: &Theme) {
+        let (title, mut lines, hint, cursor) = match self {
+            Dialog::New { form, .. } => {
+                let agent = form.agents.get(form.agent).map(String::as_str).unwrap_or("(none)");
+                let base_hint = form
+                    .current_branch
+                    .as_deref()
+                    .map(|b| format!("current branch ({})", b))
+                    .unwrap_or_else(|| "current HEAD".to_string());
+                let fields = [
+                    ("Agent", format!("‹ {} ›", agent), None),
+                    ("Name", form.name.value.clone(), Some("optional")),
+                    ("Base branch", form.base.value.clone(), Some(base_hint.as_str())),
+                    ("Agent args", form.args.value.clone(), Some("optional")),
+                ];
+                let lines: Vec<Line> = fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (label, value, placeholder))| {
+                        field_line(label, value, *placeholder, i == form.field, theme)
+                    })
+                    .collect();
+                let cursor = (form.field > 0).then(|| {
+                    let value = &fields[form.field].1;
+                    (LABEL_WIDTH + value.chars().count() as u16, form.field as u16)
+                });
+                (
+                    " New session ",
+                    lines,
+                    "Tab: next field | ←/→: agent | Enter: create | Esc: cancel",
+                    cursor,
+                )
+            }
+            Dialog::Rename { input, .. } => (
+                " Rename session ",
+                vec![field_line("Name", &input.value, None, true, theme)],
+                "Enter: rename | Esc: cancel",
+                Some((LABEL_WIDTH + input.value.chars().count() as u16, 0)),
+            ),
+            Dialog::Kill { name, .. } => (
+                " Kill session ",
+                vec![Line::from(format!("Kill session '{}' and stop its agent?", name))],
+                "y: kill and remove worktree | k: kill, keep worktree | n/Esc: cancel",
+                None,
+            ),
+            Dialog::Quit { running } => (
+                " Quit ",
+                vec![Line::from(format!("{} agent(s) still running. Quit and stop them?", running))],
+                "y: quit | n/Esc: cancel",
+                None,
+            ),
+        };
+
+        let error = match self {
+            Dialog::New { error, .. } | Dialog::Rename { error, .. } | Dialog::Kill { error, .. } => error.as_ref(),
+            Dialog::Quit { .. } => None,
+        };
+        lines.push(Line::default());
+        if let Some(error) = error {
+            for line in error.lines().filter(|l| !l.trim().is_empty()) {
+                lines.push(Line::styled(line.trim().to_string(), theme.error));
+            }
+        }
+        lines.push(Line::styled(hint, theme.muted));
+
+        let width = 72.min(f.area().width);
+        let text_width = width.saturating_sub(2).max(1) as usize;
+        // Word wrapping can take a row more than the plain character count
+        let rows: usize = lines
+            .iter()
+            .map(|l| match l.width().div_ceil(text_width) {
+                0 | 1 => 1,
+                n => n + 1,
+            })
+            .sum();
+        let height = (rows as u16 + 2).min(f.area().height);
+        let area = centered(f.area(), width, height);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(theme.border_focused);
+        let inner = block.inner(area);
+
+        f.render_widget(Clear, area);
+        f.render_widget(Paragraph::new(lines).block(block).wrap(Wrap { trim: false }), area);
+        if let Some((x, y)) = cursor {
+            f.set_cursor_position((inner.x + x.min(inner.width.saturating_sub(1)), inner.y + y));
+        }
+    }
+}
+
+const LABEL_WIDTH: u16 = 14;
+
+fn field_line(label: &str, value: &str, placeholder: Option<&str>, focused: bool, theme: &Theme) -> Line<'static> {
+    let label_style = if focused { theme.selected } else { Style::default() };
+    let value = match (value.is_empty(), placeholder) {
+        (true, Some(placeholder)) => Span::styled(placeholder.to_string(), theme.muted),
+        _ => Span::raw(value.to_string()),
+    };
+    Line::from(vec![
+        Span::styled(format!("{:<width$}", label, width = LABEL_WIDTH as usize), label_style),
+        value,
+    ])
+}
+
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    Rect {
+        x: area.x + area.width.saturating_sub(width) / 2,
+        y: area.y + area.height.saturating_sub(height) / 2,
+        width,
+        height,
+    }
+}
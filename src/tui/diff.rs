@@ -0,0 +1,570 @@
+// Diff viewer: a session's changes against its base, split into files and
+// hunks. The file list is on the left, the selected file's hunks on the
+// right, syntax highlighted by file extension.
+
+use anyhow::{bail, Result};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph},
+    Frame,
+};
+use std::cell::Cell;
+use std::path::Path;
+use std::sync::OnceLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
+use crate::config::theme::Theme;
+
+struct FileDiff {
+    path: String,
+    added: usize,
+    removed: usize,
+    /// Patch lines after the `diff --git` header: mode changes, binary
+    /// notices and hunks
+    lines: Vec<String>,
+    /// Indices into `lines` of the `@@` hunk headers
+    hunks: Vec<usize>,
+    /// A submodule whose commits are listed instead of hunks; `added` and
+    /// `removed` count commits
+    submodule: bool,
+}
+
+pub struct DiffView {
+    session: String,
+    title: String,
+    files: Vec<FileDiff>,
+    /// The patch as text when it has no per-file sections (in-memory
+    /// workspaces only report a summary)
+    summary: Vec<String>,
+    selected: usize,
+    /// First line of the selected file on screen
+    scroll: usize,
+    /// Highlighted lines per file, filled in when the file is first shown
+    highlighted: Vec<Option<Vec<Line<'static>>>>,
+    /// Rows of the hunk area at the last draw, for paging
+    page: Cell<usize>,
+    theme: Theme,
+}
+
+impl DiffView {
+    /// `patch` is unified diff output, optionally preceded by a diffstat
+    pub fn new(session: &str, title: &str, patch: &str, theme: &Theme) -> Self {
+        let files = parse(patch);
+        let summary = if files.is_empty() {
+            patch.lines().map(str::to_string).collect()
+        } else {
+            vec![]
+        };
+        let mut view = Self {
+            session: session.to_string(),
+            title: title.to_string(),
+            highlighted: files.iter().map(|_| None).collect(),
+            files,
+            summary,
+            selected: 0,
+            scroll: 0,
+            page: Cell::new(1),
+            theme: theme.clone(),
+        };
+        view.select(0);
+        view
+    }
+
+    /// Session the diff belongs to
+    pub fn session(&self) -> &str {
+        &self.session
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.summary.iter().all(|l| l.trim().is_empty())
+    }
+
+    /// Path of the file being shown
+    pub fn selected_path(&self) -> Option<&str> {
+        self.files.get(self.selected).map(|f| f.path.as_str())
+    }
+
+    /// Show `path` if the diff still has it, keeping the scroll position
+    pub fn restore(&mut self, path: &str, scroll: usize) {
+        if let Some(index) = self.files.iter().position(|f| f.path == path) {
+            self.select(index);
+            self.scroll = scroll.min(self.line_count().saturating_sub(1));
+        }
+    }
+
+    pub fn scroll_position(&self) -> usize {
+        self.scroll
+    }
+
+    fn select(&mut self, index: usize) {
+        let Some(file) = self.files.get(index) else {
+            return;
+        };
+        if self.highlighted[index].is_none() {
+            self.highlighted[index] = Some(highlight(file, &self.theme));
+        }
+        self.selected = index;
+        self.scroll = 0;
+    }
+
+    fn line_count(&self) -> usize {
+        match self.files.get(self.selected) {
+            Some(file) => file.lines.len(),
+            None => self.summary.len(),
+        }
+    }
+
+    /// Scroll by `lines`, down when positive
+    pub fn scroll(&mut self, lines: isize) {
+        let last = self.line_count().saturating_sub(1);
+        self.scroll = self.scroll.saturating_add_signed(lines).min(last);
+    }
+
+    /// Scroll by a screenful
+    pub fn page(&mut self, forward: bool) {
+        let rows = self.page.get().max(1) as isize;
+        self.scroll(if forward { rows } else { -rows });
+    }
+
+    pub fn next_file(&mut self) {
+        if self.selected + 1 < self.files.len() {
+            self.select(self.selected + 1);
+        }
+    }
+
+    pub fn prev_file(&mut self) {
+        if self.selected > 0 {
+            self.select(self.selected - 1);
+        }
+    }
+
+    /// Bring the next hunk to the top, moving on to the next file after the
+    /// last one
+    pub fn next_hunk(&mut self) {
+        let Some(file) = self.files.get(self.selected) else {
+            return;
+        };
+        match file.hunks.iter().find(|&&h| h > self.scroll) {
+            Some(&hunk) => self.scroll = hunk,
+            None if self.selected + 1 < self.files.len() => {
+                self.select(self.selected + 1);
+                self.scroll = self.files[self.selected].hunks.first().copied().unwrap_or(0);
+            }
+            None => {}
+        }
+    }
+
+    /// Bring the previous hunk to the top, moving back to the previous
+    /// file's last hunk before the first one
+    pub fn prev_hunk(&mut self) {
+        let Some(file) = self.files.get(self.selected) else {
+            return;
+        };
+        match file.hunks.iter().rev().find(|&&h| h < self.scroll) {
+            Some(&hunk) => self.scroll = hunk,
+            None if self.selected > 0 => {
+                self.select(self.selected - 1);
+                self.scroll = self.files[self.selected].hunks.last().copied().unwrap_or(0);
+            }
+            None => {}
+        }
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect) {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length((area.width / 3).clamp(20, 48)), Constraint::Min(0)])
+            .split(area);
+
+        let items: Vec<ListItem> = self
+            .files
+            .iter()
+            .enumerate()
+            .map(|(i, file)| {
+                let style = if i == self.selected {
+                    self.theme.selected
+                } else {
+                    Style::default()
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(file.path.clone(), style),
+                    Span::styled(format!(" +{}", file.added), self.theme.diff_added),
+                    Span::styled(format!(" -{}", file.removed), self.theme.diff_removed),
+                ]))
+            })
+            .collect();
+        let border = Block::default().borders(Borders::ALL).border_style(self.theme.border);
+        let files = List::new(items).block(border.clone().title(format!(
+            " {} ({}/{}) ",
+            self.title,
+            (self.selected + 1).min(self.files.len()),
+            self.files.len()
+        )));
+        f.render_widget(files, columns[0]);
+
+        let block = border.title(self.hunk_title());
+        let inner = block.inner(columns[1]);
+        self.page.set(inner.height as usize);
+
+        let rows = inner.height as usize;
+        let lines: Vec<Line> = match self.highlighted.get(self.selected).and_then(Option::as_ref) {
+            Some(lines) => lines.iter().skip(self.scroll).take(rows).cloned().collect(),
+            None => self
+                .summary
+                .iter()
+                .skip(self.scroll)
+                .take(rows)
+                .map(|l| Line::raw(l.clone()))
+                .collect(),
+        };
+        f.render_widget(Paragraph::new(lines).block(block), columns[1]);
+    }
+
+    fn hunk_title(&self) -> String {
+        let Some(file) = self.files.get(self.selected) else {
+            return " Changes ".to_string();
+        };
+        let current = file.hunks.iter().filter(|&&h| h <= self.scroll).count();
+        match file.hunks.len() {
+            0 => format!(" {} ", file.path),
+            total => format!(" {} — hunk {}/{} ", file.path, current.max(1), total),
+        }
+    }
+}
+
+/// Split `git diff` output into files; anything before the first file
+/// header (the diffstat) is skipped
+fn parse(patch: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+
+    for line in patch.lines() {
+        if let Some(header) = line.strip_prefix("diff --git ") {
+            files.push(FileDiff::new(&header_path(header), false));
+            continue;
+        }
+        // `--submodule=log` sections have no `diff --git` header
+        if let Some(path) = submodule_path(line) {
+            if !files.last().is_some_and(|f| f.submodule && f.path == path) {
+                files.push(FileDiff::new(path, true));
+            }
+            if let Some(file) = files.last_mut() {
+                file.lines.push(line.to_string());
+            }
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        if file.submodule {
+            // Commits the session added and removed, as `  > subject`
+            match line.trim_start().chars().next() {
+                Some('>') => file.added += 1,
+                Some('<') => file.removed += 1,
+                _ => {}
+            }
+            file.lines.push(line.replace('\t', "    "));
+            continue;
+        }
+        if file.hunks.is_empty() {
+            // The new name settles headers that split ambiguously
+            let new = match line.strip_prefix("+++ ") {
+                Some(path) => unquote(path).strip_prefix("b/").map(str::to_string),
+                None => ["rename to ", "copy to "].iter().find_map(|p| line.strip_prefix(p)).map(unquote),
+            };
+            if let Some(path) = new {
+                file.path = path;
+            }
+            // The old/new names and blob ids repeat the file list
+            if line.starts_with("index ") || line.starts_with("--- ") || line.starts_with("+++ ") {
+                continue;
+            }
+        }
+        if line.starts_with("@@") {
+            file.hunks.push(file.lines.len());
+        } else if !file.hunks.is_empty() {
+            match line.chars().next() {
+                Some('+') => file.added += 1,
+                Some('-') => file.removed += 1,
+                _ => {}
+            }
+        }
+        file.lines.push(line.replace('\t', "    "));
+    }
+
+    files
+}
+
+impl FileDiff {
+    fn new(path: &str, submodule: bool) -> Self {
+        Self {
+            path: path.to_string(),
+            added: 0,
+            removed: 0,
+            lines: vec![],
+            hunks: vec![],
+            submodule,
+        }
+    }
+}
+
+/// New path from the `a/<old> b/<new>` part of a `diff --git` header. Names
+/// containing " b/" only split reliably when both sides match; renames are
+/// fixed up from their `rename to` line.
+fn header_path(header: &str) -> String {
+    // Quoted new names start at the last ` "b/`
+    if let Some(i) = header.rfind(" \"b/").filter(|_| header.ends_with('"')) {
+        let new = unquote(&header[i + 1..]);
+        return new.strip_prefix("b/").unwrap_or(&new).to_string();
+    }
+    let header = header.strip_prefix("a/").unwrap_or(header);
+    let half = header.len().saturating_sub(3) / 2;
+    if header.len() == 2 * half + 3 && header.get(half..half + 3) == Some(" b/") && header[..half] == header[half + 3..] {
+        return header[half + 3..].to_string();
+    }
+    header.split_once(" b/").map(|(_, b)| b).unwrap_or(header).to_string()
+}
+
+/// A path as git writes it in patches: C-style quoted when it has control
+/// characters, quotes, backslashes or (with core.quotePath) non-ASCII bytes
+fn unquote(path: &str) -> String {
+    let Some(quoted) = path.strip_prefix('"').and_then(|p| p.strip_suffix('"')) else {
+        return path.to_string();
+    };
+    let mut bytes = Vec::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        let byte = match chars.next() {
+            Some('a') => 0x07,
+            Some('b') => 0x08,
+            Some('t') => b'\t',
+            Some('n') => b'\n',
+            Some('v') => 0x0b,
+            Some('f') => 0x0c,
+            Some('r') => b'\r',
+            Some(d @ '0'..='7') => {
+                let octal: String = std::iter::once(d).chain(chars.by_ref().take(2)).collect();
+                u8::from_str_radix(&octal, 8).unwrap_or(b'?')
+            }
+            Some(other) => other as u8,
+            None => break,
+        };
+        bytes.push(byte);
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Path named by a `--submodule=log` line: `Submodule <path> <a>..<b>:`,
+/// `Submodule <path> <a>...<b> (new submodule)` or `Submodule <path>
+/// contains modified content`
+fn submodule_path(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix("Submodule ")?;
+    for suffix in [" contains modified content", " contains untracked content"] {
+        if let Some(path) = rest.strip_suffix(suffix) {
+            return Some(path);
+        }
+    }
+    let rest = rest.strip_suffix(':').unwrap_or(rest);
+    let rest = match rest.rfind(" (") {
+        Some(i) if rest.ends_with(')') => &rest[..i],
+        _ => rest,
+    };
+    let (path, range) = rest.rsplit_once(' ')?;
+    range.contains("..").then_some(path)
+}
+
+fn assets() -> &'static (SyntaxSet, ThemeSet) {
+    static ASSETS: OnceLock<(SyntaxSet, ThemeSet)> = OnceLock::new();
+    ASSETS.get_or_init(|| (SyntaxSet::load_defaults_nonewlines(), ThemeSet::load_defaults()))
+}
+
+/// Fail on a `theme.syntax` that is not one of the bundled themes
+pub fn check_syntax_theme(theme: &Theme) -> Result<()> {
+    let Some(name) = &theme.syntax else {
+        return Ok(());
+    };
+    let (_, themes) = assets();
+    if !themes.themes.contains_key(name) {
+        let mut names: Vec<&str> = themes.themes.keys().map(String::as_str).collect();
+        names.sort();
+        bail!("Unknown theme.syntax '{}' (expected one of: {}, or none)", name, names.join(", "));
+    }
+    Ok(())
+}
+
+/// Diff lines with code colored for the file's language and added or
+/// removed lines in the theme's line styles; submodule commits in the
+/// marker colors
+fn highlight(file: &FileDiff, theme: &Theme) -> Vec<Line<'static>> {
+    if file.submodule {
+        return file
+            .lines
+            .iter()
+            .map(|line| match line.trim_start().chars().next() {
+                Some('>') => Line::styled(line.clone(), theme.diff_added),
+                Some('<') => Line::styled(line.clone(), theme.diff_removed),
+                _ => Line::styled(line.clone(), theme.muted),
+            })
+            .collect();
+    }
+    let (syntaxes, themes) = assets();
+    let syntax = Path::new(&file.path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .and_then(|e| syntaxes.find_syntax_by_extension(e))
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+    let syntax_theme = theme.syntax.as_ref().and_then(|name| themes.themes.get(name));
+    let mut highlighter = syntax_theme.map(|t| HighlightLines::new(syntax, t));
+
+    file.lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            if file.hunks.contains(&i) {
+                // Hunks are not contiguous, so parser state does not carry over
+                highlighter = syntax_theme.map(|t| HighlightLines::new(syntax, t));
+                return Line::styled(line.clone(), theme.diff_hunk);
+            }
+            let (base, marker) = match line.chars().next() {
+                Some('+') => (theme.diff_added_line, theme.diff_added_line.patch(theme.diff_added)),
+                Some('-') => (theme.diff_removed_line, theme.diff_removed_line.patch(theme.diff_removed)),
+                Some(' ') => (Style::default(), Style::default()),
+                // Mode changes, binary notices, "\ No newline at end of file"
+                _ => return Line::styled(line.clone(), theme.muted),
+            };
+            let code = &line[1..];
+            let mut spans = vec![Span::styled(line[..1].to_string(), marker)];
+            match highlighter.as_mut().map(|h| h.highlight_line(code, syntaxes)) {
+                Some(Ok(ranges)) => spans.extend(ranges.into_iter().map(|(style, text)| {
+                    let fg = style.foreground;
+                    Span::styled(text.to_string(), base.fg(Color::Rgb(fg.r, fg.g, fg.b)))
+                })),
+                // Without highlighting the whole line takes the marker's color
+                _ => spans.push(Span::styled(code.to_string(), marker)),
+            }
+            Line::from(spans).style(base)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn submodules_are_files_of_their_own() {
+        let patch = "\
+Submodule lib/first 1111111..2222222:
+  > Add the thing
+  > Fix the thing
+diff --git a/src/main.rs b/src/main.rs
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1 +1 @@
+-old
++new
+Submodule lib/second contains modified content
+Submodule lib/second 3333333..4444444 (rewind):
+  < Dropped commit
+Submodule lib/new 0000000...5555555 (new submodule)
+";
+        let files = parse(patch);
+        let summary: Vec<(&str, bool, usize, usize, usize)> = files
+            .iter()
+            .map(|f| (f.path.as_str(), f.submodule, f.added, f.removed, f.lines.len()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("lib/first", true, 2, 0, 3),
+                ("src/main.rs", false, 1, 1, 3),
+                ("lib/second", true, 0, 1, 3),
+                ("lib/new", true, 0, 0, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn files_hunks_and_counts() {
+        let patch = "\
+ src/lib.rs | 3 ++-
+ 1 file changed
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,2 +1,2 @@
+ fn a() {}
+-fn b() {}
++fn c() {}
+@@ -10 +10,2 @@
+ x
++\ty
+diff --git a/logo.png b/logo.png
+new file mode 100644
+Binary files /dev/null and b/logo.png differ
+";
+        let files = parse(patch);
+        assert_eq!(files.len(), 2);
+
+        let lib = &files[0];
+        assert_eq!((lib.path.as_str(), lib.added, lib.removed), ("src/lib.rs", 2, 1));
+        assert_eq!(lib.hunks, [0, 4]);
+        assert_eq!(lib.lines[6], "+    y");
+
+        let logo = &files[1];
+        assert_eq!((logo.path.as_str(), logo.added, logo.removed), ("logo.png", 0, 0));
+        assert_eq!(logo.lines, ["new file mode 100644", "Binary files /dev/null and b/logo.png differ"]);
+    }
+
+    #[test]
+    fn paths_that_split_ambiguously() {
+        let patch = "\
+diff --git a/x b/y b/x b/y
+old mode 100644
+new mode 100755
+diff --git a/old b/name b/new b/name
+similarity index 100%
+rename from old b/name
+rename to new b/name
+diff --git a/gone b/gone b/gone b/gone
+deleted file mode 100644
+--- a/gone b/gone
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+diff --git \"a/tab\\there\" \"b/tab\\there\"
+--- \"a/tab\\there\"
++++ \"b/tab\\there\"
+@@ -1 +1 @@
+-a
++b
+diff --git a/caf\u{e9} \"b/caf\\303\\251\"
+";
+        let paths: Vec<String> = parse(patch).into_iter().map(|f| f.path).collect();
+        assert_eq!(paths, ["x b/y", "new b/name", "gone b/gone", "tab\there", "caf\u{e9}"]);
+    }
+
+    #[test]
+    fn unquoting() {
+        assert_eq!(unquote("plain \"name\""), "plain \"name\"");
+        assert_eq!(unquote(r#""a\"b\\c\td""#), "a\"b\\c\td");
+        assert_eq!(unquote(r#""\346\227\245.txt""#), "\u{65e5}.txt");
+    }
+
+    #[test]
+    fn submodule_paths() {
+        assert_eq!(submodule_path("Submodule a b/c 1111111..2222222:"), Some("a b/c"));
+        assert_eq!(submodule_path("Submodule x (y) 1111111...2222222 (new submodule)"), Some("x (y)"));
+        assert_eq!(submodule_path("Submodule sub contains untracked content"), Some("sub"));
+        assert_eq!(submodule_path("Submodule sub"), None);
+        assert_eq!(submodule_path(" Submodule sub 1..2:"), None);
+    }
+}
@@ -0,0 +1,152 @@
+// Everything the TUI reacts to, funnelled into one channel: terminal input,
+// session state files changing on disk (other acta commands write them),
+// the stored layout being replaced, agents producing output, and work done
+// in the background finishing.
+
+use anyhow::Result;
+use crossterm::event;
+use notify::event::{AccessKind, AccessMode};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tracing::warn;
+use crate::session::overlap::Overlap;
+
+pub enum AppEvent {
+    Input(event::Event),
+    /// A session state file changed
+    Sessions,
+    /// A session's log or inbox changed; state needs no re-reading
+    Activity,
+    /// The stored layout changed
+    Layout,
+    /// An agent wrote to its terminal or exited
+    Output,
+    /// Something for the footer from work done in the background
+    Notice(String),
+    /// Creating the session with this id finished, or failed before it
+    /// was saved
+    Created(String, Result<()>),
+    /// Overlapping edits found by a background check
+    Overlaps(Vec<Overlap>),
+    /// Nothing happened for a while; time to refresh uptimes, and to
+    /// re-read state when it is not being watched
+    Tick,
+}
+
+pub struct Events {
+    tx: Sender<AppEvent>,
+    rx: Receiver<AppEvent>,
+    /// Set while an Output event is queued, so bursts of output wake the
+    /// loop once
+    output: Arc<AtomicBool>,
+    /// None when watching failed
+    watcher: Option<RecommendedWatcher>,
+}
+
+impl Events {
+    pub fn new(state_dir: &Path, layout_path: &Path) -> Self {
+        let (tx, rx) = mpsc::channel();
+
+        let input = tx.clone();
+        thread::spawn(move || {
+            while let Ok(event) = event::read() {
+                if input.send(AppEvent::Input(event)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let watcher = match watch(tx.clone(), state_dir, layout_path) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                warn!("Not watching {} for changes: {:#}", state_dir.display(), e);
+                None
+            }
+        };
+
+        Self {
+            tx,
+            rx,
+            output: Arc::new(AtomicBool::new(false)),
+            watcher,
+        }
+    }
+
+    /// Whether state changes arrive as events, or have to be polled on Tick
+    pub fn watching(&self) -> bool {
+        self.watcher.is_some()
+    }
+
+    /// Callback for agents to report new output
+    pub fn output_notifier(&self) -> impl Fn() + Send + 'static {
+        let (tx, pending) = (self.tx.clone(), self.output.clone());
+        move || {
+            if !pending.swap(true, Ordering::AcqRel) {
+                let _ = tx.send(AppEvent::Output);
+            }
+        }
+    }
+
+    /// Channel for background work to report back on
+    pub fn sender(&self) -> Sender<AppEvent> {
+        self.tx.clone()
+    }
+
+    /// Wait up to `tick` for the next event
+    pub fn next(&self, tick: Duration) -> AppEvent {
+        self.received(self.rx.recv_timeout(tick))
+    }
+
+    /// An event that is already waiting, without blocking
+    pub fn pending(&self) -> Option<AppEvent> {
+        self.rx.try_recv().ok().map(|e| self.received(Ok(e)))
+    }
+
+    fn received(&self, event: Result<AppEvent, RecvTimeoutError>) -> AppEvent {
+        match event {
+            Ok(AppEvent::Output) => {
+                self.output.store(false, Ordering::Release);
+                AppEvent::Output
+            }
+            Ok(event) => event,
+            Err(_) => AppEvent::Tick,
+        }
+    }
+}
+
+fn watch(tx: Sender<AppEvent>, state_dir: &Path, layout_path: &Path) -> Result<RecommendedWatcher> {
+    let sessions: PathBuf = state_dir.to_path_buf();
+    let layout: PathBuf = layout_path.to_path_buf();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else {
+            return;
+        };
+        // Reads don't change anything, but a file closed after writing is
+        // the moment its contents are complete
+        if matches!(event.kind, EventKind::Access(kind) if kind != AccessKind::Close(AccessMode::Write)) {
+            return;
+        }
+        if event.paths.iter().any(|p| p == &layout) {
+            let _ = tx.send(AppEvent::Layout);
+        }
+        let in_state_dir: Vec<&PathBuf> = event.paths.iter().filter(|p| p.parent() == Some(sessions.as_path())).collect();
+        if in_state_dir.iter().any(|p| p.extension().is_some_and(|e| e == "json")) {
+            let _ = tx.send(AppEvent::Sessions);
+        } else if !in_state_dir.is_empty() {
+            let _ = tx.send(AppEvent::Activity);
+        }
+    })?;
+
+    watcher.watch(state_dir, RecursiveMode::NonRecursive)?;
+    if let Some(dir) = layout_path.parent() {
+        fs::create_dir_all(dir)?;
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+    Ok(watcher)
+}
@@ -0,0 +1,166 @@
+// Session list filtering and ordering: fuzzy matching over the fields a
+// session is known by, and the orders the list cycles through
+
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use std::cmp::Reverse;
+use std::time::SystemTime;
+use crate::session::{Session, SessionStatus};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SortKey {
+    #[default]
+    Created,
+    /// Most recently active first
+    Activity,
+    Agent,
+    /// Sessions needing attention first
+    Status,
+}
+
+impl SortKey {
+    pub fn name(self) -> &'static str {
+        match self {
+            SortKey::Created => "created",
+            SortKey::Activity => "last activity",
+            SortKey::Agent => "agent",
+            SortKey::Status => "status",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            SortKey::Created => SortKey::Activity,
+            SortKey::Activity => SortKey::Agent,
+            SortKey::Agent => SortKey::Status,
+            SortKey::Status => SortKey::Created,
+        }
+    }
+}
+
+/// Whether every whitespace-separated term of `query` fuzzy-matches the
+/// session's name, agent, branch, one of its tags or its status
+pub fn matches(matcher: &SkimMatcherV2, session: &Session, query: &str) -> bool {
+    let status = format!("{:?}", session.status);
+    let fields: Vec<&str> = [session.display_name(), &session.agent, &status]
+        .into_iter()
+        .chain(session.branch.as_deref())
+        .chain(session.tags.iter().map(String::as_str))
+        .collect();
+
+    query
+        .split_whitespace()
+        .all(|term| fields.iter().any(|field| matcher.fuzzy_match(field, term).is_some()))
+}
+
+/// Order `sessions`, given in creation order, by `key`; ties keep creation
+/// order
+pub fn sort(sessions: &mut [&Session], key: SortKey, activity: impl Fn(&Session) -> SystemTime) {
+    match key {
+        SortKey::Created => {}
+        SortKey::Activity => sessions.sort_by_cached_key(|s| Reverse(activity(s))),
+        SortKey::Agent => sessions.sort_by(|a, b| a.agent.cmp(&b.agent)),
+        SortKey::Status => sessions.sort_by_key(|s| status_rank(&s.status)),
+    }
+}
+
+fn status_rank(status: &SessionStatus) -> u8 {
+    match status {
+        SessionStatus::NeedsInput => 0,
+        SessionStatus::Conflicted => 1,
+        SessionStatus::Failed => 2,
+        SessionStatus::Running => 3,
+        SessionStatus::Preparing => 4,
+        SessionStatus::Stopped => 5,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::isolation::BackendKind;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn session(name: &str, agent: &str, status: SessionStatus) -> Session {
+        Session {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: Some(name.to_string()),
+            agent: agent.to_string(),
+            worktree_path: PathBuf::from("/tmp").join(name),
+            status,
+            created_at: SystemTime::UNIX_EPOCH,
+            args: vec![],
+            repo_path: None,
+            branch: Some(format!("acta/{}", name)),
+            base_commit: None,
+            base_branch: None,
+            source_path: None,
+            owns_branch: true,
+            backend: BackendKind::Worktree,
+            allow_push: false,
+            sparse: vec![],
+            tags: vec!["frontend".to_string()],
+            waiting_since: None,
+            status_before_sync: None,
+        }
+    }
+
+    fn names(sessions: &[&Session]) -> Vec<String> {
+        sessions.iter().map(|s| s.display_name().to_string()).collect()
+    }
+
+    #[test]
+    fn every_term_matches_some_field() {
+        let matcher = SkimMatcherV2::default();
+        let session = session("login-form", "claude", SessionStatus::NeedsInput);
+        let matches = |query| matches(&matcher, &session, query);
+
+        assert!(matches(""));
+        assert!(matches("lgnfrm"));
+        assert!(matches("claude needs"));
+        assert!(matches("acta/login"));
+        assert!(matches("front cla"));
+        assert!(!matches("login opencode"));
+        assert!(!matches("running"));
+    }
+
+    #[test]
+    fn sorts_keep_creation_order_for_ties() {
+        let sessions = [
+            session("a", "opencode", SessionStatus::Running),
+            session("b", "claude", SessionStatus::Stopped),
+            session("c", "opencode", SessionStatus::NeedsInput),
+            session("d", "claude", SessionStatus::Running),
+        ];
+        let active = |s: &Session| {
+            let seconds = match s.display_name() {
+                "b" | "d" => 20,
+                _ => 10,
+            };
+            SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)
+        };
+        let sorted = |key| {
+            let mut list: Vec<&Session> = sessions.iter().collect();
+            sort(&mut list, key, active);
+            names(&list)
+        };
+
+        assert_eq!(sorted(SortKey::Created), ["a", "b", "c", "d"]);
+        assert_eq!(sorted(SortKey::Activity), ["b", "d", "a", "c"]);
+        assert_eq!(sorted(SortKey::Agent), ["b", "d", "a", "c"]);
+        assert_eq!(sorted(SortKey::Status), ["c", "a", "d", "b"]);
+    }
+
+    #[test]
+    fn sort_keys_cycle_through_all() {
+        let mut key = SortKey::default();
+        let mut seen = vec![];
+        for _ in 0..4 {
+            seen.push(key.name());
+            key = key.next();
+        }
+        assert_eq!(key, SortKey::default());
+        assert_eq!(seen, ["created", "last activity", "agent", "status"]);
+    }
+}
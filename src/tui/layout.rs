@@ -0,0 +1,386 @@
+// Tiled pane layout: a tree of splits with one session per leaf
+//
+// The TUI keeps its current layout in ~/.acta/layout.yaml so it survives
+// restarts; `acta layout save/load` copy it to and from named layouts.
+
+use anyhow::{bail, Context, Result};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Split {
+    /// Side by side
+    Horizontal,
+    /// Stacked
+    Vertical,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Node {
+    /// A pane showing the session with this ID
+    Pane(String),
+    Split { direction: Split, children: Vec<Node> },
+}
+
+/// Arrangements applied to the panes on screen, cycled from the TUI
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Preset {
+    /// All panes side by side
+    EvenHorizontal,
+    /// All panes stacked
+    EvenVertical,
+    /// The first pane on the left, the others stacked on the right
+    MainVertical,
+    /// A grid of roughly equal rows and columns
+    Tiled,
+}
+
+impl Preset {
+    pub const ALL: &'static [Preset] = &[
+        Preset::EvenHorizontal,
+        Preset::EvenVertical,
+        Preset::MainVertical,
+        Preset::Tiled,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::EvenHorizontal => "even-horizontal",
+            Preset::EvenVertical => "even-vertical",
+            Preset::MainVertical => "main-vertical",
+            Preset::Tiled => "tiled",
+        }
+    }
+
+    pub fn next(self) -> Preset {
+        let i = Preset::ALL.iter().position(|p| *p == self).unwrap_or(0);
+        Preset::ALL[(i + 1) % Preset::ALL.len()]
+    }
+
+    /// Arrange `panes` (session IDs, in order) this way
+    pub fn arrange(self, panes: &[&str]) -> Option<Node> {
+        let leaves = |ids: &[&str]| ids.iter().map(|id| Node::Pane(id.to_string())).collect::<Vec<_>>();
+        let node = match (self, panes) {
+            (_, []) => return None,
+            (_, [id]) => Node::Pane(id.to_string()),
+            (Preset::EvenHorizontal, _) => Node::group(Split::Horizontal, leaves(panes)),
+            (Preset::EvenVertical, _) => Node::group(Split::Vertical, leaves(panes)),
+            (Preset::MainVertical, [main, rest @ ..]) => Node::group(
+                Split::Horizontal,
+                vec![
+                    Node::Pane(main.to_string()),
+                    Node::group(Split::Vertical, leaves(rest)),
+                ],
+            ),
+            (Preset::Tiled, _) => {
+                let columns = (1..).find(|c| c * c >= panes.len()).unwrap_or(1);
+                let rows = panes
+                    .chunks(columns)
+                    .map(|row| Node::group(Split::Horizontal, leaves(row)))
+                    .collect();
+                Node::group(Split::Vertical, rows)
+            }
+        };
+        Some(node)
+    }
+}
+
+impl Node {
+    /// A split of `children`, or the only child itself
+    fn group(direction: Split, mut children: Vec<Node>) -> Node {
+        if children.len() == 1 {
+            return children.remove(0);
+        }
+        Node::Split {
+            direction,
+            children,
+        }
+    }
+
+    /// Session IDs of all panes, in reading order
+    pub fn panes(&self) -> Vec<&str> {
+        match self {
+            Node::Pane(id) => vec![id],
+            Node::Split { children, .. } => children.iter().flat_map(Node::panes).collect(),
+        }
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.panes().contains(&id)
+    }
+
+    /// Drop panes whose session is gone; None when nothing is left
+    pub fn retain(self, keep: impl Fn(&str) -> bool) -> Option<Node> {
+        let gone: Vec<String> = self
+            .panes()
+            .into_iter()
+            .filter(|id| !keep(id))
+            .map(str::to_string)
+            .collect();
+        gone.iter().try_fold(self, |node, id| node.remove(id))
+    }
+
+    /// Split the pane showing `target` and show `id` next to it. Splitting
+    /// in the direction of the enclosing split adds an evenly sized sibling.
+    pub fn split(&mut self, target: &str, id: String, direction: Split) {
+        match self {
+            Node::Pane(current) if current == target => {
+                let existing = Node::Pane(std::mem::take(current));
+                *self = Node::Split {
+                    direction,
+                    children: vec![existing, Node::Pane(id)],
+                };
+            }
+            Node::Pane(_) => {}
+            Node::Split {
+                direction: own,
+                children,
+            } => {
+                let position = children
+                    .iter()
+                    .position(|c| matches!(c, Node::Pane(p) if p == target));
+                match position {
+                    Some(i) if *own == direction => children.insert(i + 1, Node::Pane(id)),
+                    _ => {
+                        if let Some(child) = children.iter_mut().find(|c| c.contains(target)) {
+                            child.split(target, id, direction);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The layout without the pane showing `id`; None when nothing is left
+    pub fn remove(self, id: &str) -> Option<Node> {
+        match self {
+            Node::Pane(current) if current == id => None,
+            Node::Pane(_) => Some(self),
+            Node::Split {
+                direction,
+                children,
+            } => {
+                let mut children: Vec<Node> =
+                    children.into_iter().filter_map(|c| c.remove(id)).collect();
+                match children.len() {
+                    0 => None,
+                    1 => children.pop(),
+                    _ => Some(Node::Split {
+                        direction,
+                        children,
+                    }),
+                }
+            }
+        }
+    }
+
+    /// Screen area of every pane within `area`
+    pub fn areas(&self, area: Rect) -> Vec<(&str, Rect)> {
+        match self {
+            Node::Pane(id) => vec![(id.as_str(), area)],
+            Node::Split {
+                direction,
+                children,
+            } => {
+                let direction = match direction {
+                    Split::Horizontal => Direction::Horizontal,
+                    Split::Vertical => Direction::Vertical,
+                };
+                let count = children.len() as u32;
+                let chunks = Layout::default()
+                    .direction(direction)
+                    .constraints(children.iter().map(|_| Constraint::Ratio(1, count)))
+                    .split(area);
+                children
+                    .iter()
+                    .zip(chunks.iter())
+                    .flat_map(|(child, chunk)| child.areas(*chunk))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Directory holding named layouts
+fn layouts_dir() -> Result<PathBuf> {
+    let home = dirs::home_dir().context("Could not determine home directory")?;
+    Ok(home.join(".acta").join("layouts"))
+}
+
+/// Where the TUI keeps the layout on screen
+pub fn current_path() -> Result<PathBuf> {
+    let home = dirs::home_dir().context("Could not determine home directory")?;
+    Ok(home.join(".acta").join("layout.yaml"))
+}
+
+pub fn named_path(name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        bail!("Invalid layout name '{}'", name);
+    }
+    Ok(layouts_dir()?.join(format!("{}.yaml", name)))
+}
+
+/// Names of saved layouts
+pub fn saved() -> Result<Vec<String>> {
+    let dir = layouts_dir()?;
+    if !dir.exists() {
+        return Ok(vec![]);
+    }
+    let mut names: Vec<String> = fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            (path.extension()? == "yaml").then(|| path.file_stem()?.to_str().map(str::to_string))?
+        })
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Layout stored at `path`; None when there is none
+pub fn load(path: &Path) -> Result<Option<Node>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read layout from {}", path.display()))?;
+    let node = serde_yaml::from_str(&contents)
+        .with_context(|| format!("Failed to parse layout from {}", path.display()))?;
+    Ok(node)
+}
+
+/// Store `layout` at `path`, removing the file when there is none
+pub fn save(path: &Path, layout: Option<&Node>) -> Result<()> {
+    let Some(layout) = layout else {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        return Ok(());
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_yaml::to_string(layout)?)
+        .with_context(|| format!("Failed to write layout to {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pane(id: &str) -> Node {
+        Node::Pane(id.to_string())
+    }
+
+    fn split(direction: Split, children: Vec<Node>) -> Node {
+        Node::Split { direction, children }
+    }
+
+    #[test]
+    fn split_nests_across_and_extends_along() {
+        let mut layout = pane("a");
+        layout.split("a", "b".to_string(), Split::Horizontal);
+        assert_eq!(layout, split(Split::Horizontal, vec![pane("a"), pane("b")]));
+
+        // Same direction: an evenly sized sibling right after the target
+        layout.split("a", "c".to_string(), Split::Horizontal);
+        assert_eq!(layout, split(Split::Horizontal, vec![pane("a"), pane("c"), pane("b")]));
+
+        // Across: the target pane itself becomes a split
+        layout.split("c", "d".to_string(), Split::Vertical);
+        assert_eq!(
+            layout,
+            split(
+                Split::Horizontal,
+                vec![pane("a"), split(Split::Vertical, vec![pane("c"), pane("d")]), pane("b")]
+            )
+        );
+        assert_eq!(layout.panes(), ["a", "c", "d", "b"]);
+
+        // Unknown targets leave the layout alone
+        let before = layout.clone();
+        layout.split("zz", "e".to_string(), Split::Vertical);
+        assert_eq!(layout, before);
+    }
+
+    #[test]
+    fn remove_collapses_single_children() {
+        let layout = split(
+            Split::Horizontal,
+            vec![pane("a"), split(Split::Vertical, vec![pane("c"), pane("d")])],
+        );
+
+        let without_c = layout.clone().remove("c").unwrap();
+        assert_eq!(without_c, split(Split::Horizontal, vec![pane("a"), pane("d")]));
+        assert_eq!(without_c.remove("a"), Some(pane("d")));
+        assert_eq!(pane("a").remove("a"), None);
+        assert_eq!(layout.clone().remove("zz"), Some(layout.clone()));
+
+        let kept = layout.retain(|id| id == "d");
+        assert_eq!(kept, Some(pane("d")));
+    }
+
+    #[test]
+    fn presets_arrange_panes_in_order() {
+        let ids = ["a", "b", "c"];
+        assert_eq!(Preset::Tiled.arrange(&[]), None);
+        assert_eq!(Preset::MainVertical.arrange(&["a"]), Some(pane("a")));
+        assert_eq!(
+            Preset::EvenHorizontal.arrange(&ids),
+            Some(split(Split::Horizontal, vec![pane("a"), pane("b"), pane("c")]))
+        );
+        assert_eq!(
+            Preset::EvenVertical.arrange(&ids),
+            Some(split(Split::Vertical, vec![pane("a"), pane("b"), pane("c")]))
+        );
+        assert_eq!(
+            Preset::MainVertical.arrange(&ids),
+            Some(split(
+                Split::Horizontal,
+                vec![pane("a"), split(Split::Vertical, vec![pane("b"), pane("c")])]
+            ))
+        );
+        // Two columns for three panes; a lone pane in the last row spans it
+        assert_eq!(
+            Preset::Tiled.arrange(&ids),
+            Some(split(
+                Split::Vertical,
+                vec![split(Split::Horizontal, vec![pane("a"), pane("b")]), pane("c")]
+            ))
+        );
+        for preset in Preset::ALL {
+            let five = ["1", "2", "3", "4", "5"];
+            assert_eq!(preset.arrange(&five).unwrap().panes(), five);
+        }
+    }
+
+    #[test]
+    fn presets_cycle_through_all() {
+        let mut preset = Preset::ALL[0];
+        for expected in Preset::ALL.iter().cycle().skip(1).take(Preset::ALL.len()) {
+            preset = preset.next();
+            assert_eq!(preset, *expected);
+        }
+    }
+
+    #[test]
+    fn areas_share_the_space_evenly() {
+        let layout = split(
+            Split::Horizontal,
+            vec![pane("a"), split(Split::Vertical, vec![pane("b"), pane("c")])],
+        );
+        let areas = layout.areas(Rect::new(0, 0, 80, 20));
+        assert_eq!(
+            areas,
+            [
+                ("a", Rect::new(0, 0, 40, 20)),
+                ("b", Rect::new(40, 0, 40, 10)),
+                ("c", Rect::new(40, 10, 40, 10)),
+            ]
+        );
+    }
+}
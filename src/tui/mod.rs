@@ -1,51 +1,1129 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use fuzzy_matcher::skim::SkimMatcherV2;
 use ratatui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
     Frame, Terminal,
 };
-use std::io;
-use crate::session::SessionManager;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use uuid::Uuid;
+use crate::config::keys::{Action, Chord, Keymap, Lookup};
+use crate::config::theme::Theme;
+use crate::config::Config;
+use crate::git;
+use crate::git::checkpoint::{self, Schedule};
+use crate::session::agent::Agent;
+use crate::session::overlap::{self, Overlap};
+use crate::session::{NewSession, Session, SessionManager, SessionStatus};
+
+mod copy;
+mod dialog;
+mod diff;
+mod events;
+mod filter;
+pub mod layout;
+mod pane;
+
+use copy::CopyMode;
+use dialog::{Dialog, Input, NewSessionForm, Outcome};
+use diff::DiffView;
+use events::{AppEvent, Events};
+use filter::SortKey;
+use layout::{Node, Preset, Split};
+
+/// How often uptimes are refreshed when nothing else happens
+const TICK: Duration = Duration::from_secs(1);
+
+/// Least time between overlap checks, which diff every running session
+const OVERLAP_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum View {
+    List,
+    Panes,
+    /// One session full screen, with keys going to its agent
+    Attached,
+    /// A session's changes against its base
+    Diff,
+}
 
 pub struct App {
     manager: SessionManager,
+    config: Config,
+    /// Session ids in list order, filtered and sorted; `selected` indexes it
+    list: Vec<String>,
     selected: usize,
+    filter: Input,
+    /// Keys go to the filter
+    filtering: bool,
+    sort: SortKey,
+    matcher: SkimMatcherV2,
+    overlaps: Vec<Overlap>,
+    /// Sessions changed or agents worked since overlaps were last checked
+    overlaps_due: bool,
+    /// When the last overlap check started, and whether it still runs
+    overlaps_checked: Option<Instant>,
+    checking_overlaps: bool,
+    view: View,
+    layout: Option<Node>,
+    /// Index of the focused pane in `layout`
+    focus: usize,
+    zoomed: bool,
+    /// Last preset applied with the layout action
+    preset: Option<Preset>,
+    /// Where the layout is kept between runs, and what was last written or
+    /// read there; `acta layout load` replaces the file while the TUI runs
+    layout_path: PathBuf,
+    layout_stamp: Option<SystemTime>,
+    stored_layout: Option<Node>,
+    /// Keys are forwarded to the focused pane's agent
+    typing: bool,
+    /// Session shown in the attached view
+    attached: Option<String>,
+    keymap: Keymap,
+    theme: Theme,
+    /// Keys of a sequence typed so far
+    pending: Vec<KeyEvent>,
+    agents: HashMap<String, Agent>,
+    /// Automatic checkpoints of sessions with a running agent
+    schedules: HashMap<String, Schedule>,
+    /// Sessions being checkpointed in the background
+    checkpointing: Arc<Mutex<HashSet<String>>>,
+    /// Sessions being created in the background, placed where the user is
+    /// once they are saved
+    creating: HashSet<String>,
+    /// Why a session has no running agent (exited, failed to start)
+    ended: HashMap<String, String>,
+    message: Option<String>,
+    /// Modal dialog taking all keys while open
+    dialog: Option<Dialog>,
+    diff: Option<DiffView>,
+    /// View to return to when the diff closes
+    diff_from: View,
+    /// Scrollback snapshot of the focused or attached session, taking all
+    /// keys while open
+    copy: Option<CopyMode>,
+    events: Events,
 }
 
 impl App {
     pub fn new() -> Result<Self> {
-        Ok(Self {
-            manager: SessionManager::new()?,
+        let mut manager = SessionManager::open()?;
+        let layout_path = layout::current_path()?;
+        let events = Events::new(manager.state_dir(), &layout_path);
+        // Catch sessions saved before the watcher started
+        manager.reload()?;
+        let config = Config::load()?;
+        let keymap = config.keymap()?;
+        let theme = config.theme()?;
+        diff::check_syntax_theme(&theme)?;
+        let mut app = Self {
+            manager,
+            config,
+            list: vec![],
             selected: 0,
-        })
+            filter: Input::default(),
+            filtering: false,
+            sort: SortKey::default(),
+            matcher: SkimMatcherV2::default(),
+            overlaps: vec![],
+            overlaps_due: true,
+            overlaps_checked: None,
+            checking_overlaps: false,
+            view: View::List,
+            layout: None,
+            focus: 0,
+            zoomed: false,
+            preset: None,
+            layout_path,
+            layout_stamp: None,
+            stored_layout: None,
+            typing: false,
+            attached: None,
+            keymap,
+            theme,
+            pending: vec![],
+            agents: HashMap::new(),
+            schedules: HashMap::new(),
+            checkpointing: Arc::default(),
+            creating: HashSet::new(),
+            ended: HashMap::new(),
+            message: None,
+            dialog: None,
+            diff: None,
+            diff_from: View::List,
+            copy: None,
+            events,
+        };
+        app.refresh_list();
+        Ok(app)
+    }
+
+    /// Sessions in list order
+    fn sessions(&self) -> Vec<&Session> {
+        self.list.iter().filter_map(|id| self.manager.get_session(id)).collect()
+    }
+
+    fn selected_id(&self) -> Option<String> {
+        self.list.get(self.selected).cloned()
+    }
+
+    /// Filter and sort the list again, keeping the selection on the same
+    /// session, or at the same position when it dropped out
+    fn refresh_list(&mut self) {
+        let selected = self.selected_id();
+        let query = self.filter.value();
+        let mut sessions: Vec<&Session> = self
+            .manager
+            .list_sessions()
+            .into_iter()
+            .filter(|s| filter::matches(&self.matcher, s, query))
+            .collect();
+        filter::sort(&mut sessions, self.sort, |s| self.last_activity(s));
+        self.list = sessions.iter().map(|s| s.id.clone()).collect();
+
+        self.selected = selected
+            .and_then(|id| self.list.iter().position(|s| *s == id))
+            .unwrap_or(self.selected)
+            .min(self.list.len().saturating_sub(1));
+    }
+
+    /// Latest of creation, state changes on disk and agent output
+    fn last_activity(&self, session: &Session) -> SystemTime {
+        [
+            self.manager.last_activity(&session.id),
+            self.agents.get(&session.id).and_then(Agent::last_output),
+        ]
+        .into_iter()
+        .flatten()
+        .fold(session.created_at, SystemTime::max)
+    }
+
+    fn edit_filter(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter => self.filtering = false,
+            KeyCode::Esc => {
+                self.filtering = false;
+                self.filter = Input::default();
+            }
+            KeyCode::Down => self.select_next(),
+            KeyCode::Up => self.select_previous(),
+            _ => self.filter.handle_key(key),
+        }
+        self.refresh_list();
+    }
+
+    /// Re-read sessions after another acta process changed them, keeping
+    /// the selection on the same session and dropping killed ones
+    fn reload_sessions(&mut self) -> Result<()> {
+        let waiting: HashSet<String> = self.waiting().iter().map(|s| s.id.clone()).collect();
+        self.manager.reload()?;
+        if self.config.tui.bell && self.waiting().iter().any(|s| !waiting.contains(&s.id)) {
+            let mut stdout = io::stdout();
+            stdout.write_all(b"\x07")?;
+            stdout.flush()?;
+        }
+        self.refresh_list();
+
+        let ids: HashSet<String> = self.manager.list_sessions().iter().map(|s| s.id.clone()).collect();
+        let exists = |id: &str| ids.contains(id);
+        self.agents.retain(|id, _| exists(id));
+        self.ended.retain(|id, _| exists(id));
+        if self.layout.as_ref().is_some_and(|l| l.panes().iter().any(|id| !exists(id))) {
+            let focused = self.focused();
+            self.layout = self.layout.take().and_then(|l| l.retain(exists));
+            self.zoomed = false;
+            match (&self.layout, focused) {
+                (Some(_), Some(id)) if exists(&id) => self.focus_session(&id),
+                (Some(_), _) => {
+                    self.focus = 0;
+                    self.typing = false;
+                }
+                (None, _) => {}
+            }
+        }
+        if self.diff.as_ref().is_some_and(|d| !exists(d.session())) {
+            self.close_diff();
+        }
+        if self.attached.as_deref().is_some_and(|id| !exists(id)) {
+            self.attached = None;
+            self.view = View::List;
+        }
+        if self.layout.is_none() && self.view == View::Panes {
+            self.typing = false;
+            self.view = View::List;
+        }
+        if self.dialog.as_ref().is_some_and(|d| d.session().is_some_and(|id| !exists(id))) {
+            self.dialog = None;
+        }
+        if self.copy.as_ref().is_some_and(|c| !exists(c.session())) {
+            self.copy = None;
+        }
+        let saved: Vec<String> = self.creating.iter().filter(|id| exists(id)).cloned().collect();
+        for id in saved {
+            self.creating.remove(&id);
+            self.place(id);
+        }
+
+        self.overlaps.retain(|o| exists(&o.first) && exists(&o.second));
+        self.overlaps_due = true;
+        Ok(())
+    }
+
+    /// Pick up the stored layout when it changed on disk
+    fn reload_layout(&mut self) {
+        let stamp = fs::metadata(&self.layout_path).and_then(|m| m.modified()).ok();
+        if stamp == self.layout_stamp {
+            return;
+        }
+        self.layout_stamp = stamp;
+
+        let stored = match layout::load(&self.layout_path) {
+            Ok(stored) => stored,
+            Err(e) => {
+                self.message = Some(format!("{:#}", e));
+                return;
+            }
+        };
+        if stored == self.stored_layout {
+            return;
+        }
+        self.stored_layout = stored.clone();
+        self.layout = stored.and_then(|l| l.retain(|id| self.manager.get_session(id).is_some()));
+        self.focus = 0;
+        self.zoomed = false;
+        self.typing = false;
+        if self.view == View::Panes {
+            self.copy = None;
+        }
+        if self.layout.is_none() && self.view == View::Panes {
+            self.view = View::List;
+        }
+    }
+
+    /// Write the layout out when it changed
+    fn store_layout(&mut self) -> Result<()> {
+        if self.layout == self.stored_layout {
+            return Ok(());
+        }
+        layout::save(&self.layout_path, self.layout.as_ref())?;
+        self.stored_layout = self.layout.clone();
+        self.layout_stamp = fs::metadata(&self.layout_path).and_then(|m| m.modified()).ok();
+        Ok(())
+    }
+
+    /// Rearrange the panes with the next preset, keeping focus on the same session
+    fn cycle_preset(&mut self) {
+        let Some(layout) = &self.layout else {
+            return;
+        };
+        let focused = self.focused();
+        let preset = self.preset.map(Preset::next).unwrap_or(Preset::ALL[0]);
+        self.layout = preset.arrange(&layout.panes());
+        self.preset = Some(preset);
+        self.zoomed = false;
+        if let Some(id) = focused {
+            self.focus_session(&id);
+        }
+        self.message = Some(format!("Layout: {}", preset.name()));
     }
 
     fn select_next(&mut self) {
-        let sessions = self.manager.list_sessions();
-        if !sessions.is_empty() {
-            self.selected = (self.selected + 1) % sessions.len();
+        if !self.list.is_empty() {
+            self.selected = (self.selected + 1) % self.list.len();
         }
     }
 
     fn select_previous(&mut self) {
-        let sessions = self.manager.list_sessions();
-        if !sessions.is_empty() {
+        if !self.list.is_empty() {
             if self.selected > 0 {
                 self.selected -= 1;
             } else {
-                self.selected = sessions.len() - 1;
+                self.selected = self.list.len() - 1;
+            }
+        }
+    }
+
+    /// Session whose agent receives keys in the current view
+    fn target(&self) -> Option<String> {
+        match self.view {
+            View::Attached => self.attached.clone(),
+            _ => self.focused(),
+        }
+    }
+
+    fn focused(&self) -> Option<String> {
+        let layout = self.layout.as_ref()?;
+        layout.panes().get(self.focus).map(|id| id.to_string())
+    }
+
+    fn focus_session(&mut self, id: &str) {
+        if let Some(layout) = &self.layout {
+            self.focus = layout.panes().iter().position(|p| *p == id).unwrap_or(0);
+        }
+    }
+
+    /// Show the selected session in a pane, next to the focused one
+    fn open_selected(&mut self) {
+        if let Some(id) = self.selected_id() {
+            self.open_pane(id);
+        }
+    }
+
+    /// Show a session in a pane next to the focused one, or focus its pane
+    fn open_pane(&mut self, id: String) {
+        let focused = self.focused();
+        match (&mut self.layout, focused) {
+            (Some(layout), _) if layout.contains(&id) => {}
+            (Some(layout), Some(target)) => layout.split(&target, id.clone(), Split::Horizontal),
+            _ => self.layout = Some(Node::Pane(id.clone())),
+        }
+        self.focus_session(&id);
+        self.typing = false;
+        self.view = View::Panes;
+    }
+
+    /// Sessions waiting for input, the longest waiting first
+    fn waiting(&self) -> Vec<&Session> {
+        let mut waiting: Vec<&Session> = self
+            .manager
+            .list_sessions()
+            .into_iter()
+            .filter(|s| s.status == SessionStatus::NeedsInput)
+            .collect();
+        waiting.sort_by_key(|s| s.waiting_since.unwrap_or(s.created_at));
+        waiting
+    }
+
+    /// Go to the waiting session after the current one, in the way the
+    /// current view shows sessions
+    fn next_waiting(&mut self) {
+        let queue: Vec<String> = self.waiting().iter().map(|s| s.id.clone()).collect();
+        if queue.is_empty() {
+            self.message = Some("No sessions waiting for input".to_string());
+            return;
+        }
+        let next = self
+            .current()
+            .and_then(|current| queue.iter().position(|id| *id == current))
+            .map(|i| (i + 1) % queue.len())
+            .unwrap_or(0);
+        let id = queue[next].clone();
+
+        match self.view {
+            View::Attached => self.attach(id),
+            View::Panes if self.layout.as_ref().is_some_and(|l| l.contains(&id)) => {
+                self.zoomed &= self.focused().as_deref() == Some(id.as_str());
+                self.focus_session(&id);
+                self.typing &= self.agents.contains_key(&id);
+            }
+            View::Panes => self.open_pane(id),
+            View::List | View::Diff => {
+                self.close_diff();
+                if !self.list.contains(&id) {
+                    self.filter = Input::default();
+                    self.refresh_list();
+                }
+                if let Some(i) = self.list.iter().position(|s| *s == id) {
+                    self.selected = i;
+                }
+            }
+        }
+    }
+
+    fn attach(&mut self, id: String) {
+        if let Some(i) = self.list.iter().position(|s| *s == id) {
+            self.selected = i;
+        }
+        self.attached = Some(id);
+        self.view = View::Attached;
+    }
+
+    /// Leave the agent: back to the list when attached, to pane navigation otherwise
+    fn detach(&mut self) {
+        match self.view {
+            View::Attached => {
+                self.attached = None;
+                self.view = View::List;
+            }
+            _ => self.typing = false,
+        }
+    }
+
+    /// Attach the next or previous session, or move pane focus
+    fn switch(&mut self, forward: bool) {
+        if self.view != View::Attached {
+            self.cycle_focus(forward);
+            if self.typing {
+                self.typing = self.target().is_some_and(|id| self.agents.contains_key(&id));
+            }
+            return;
+        }
+        if forward {
+            self.select_next();
+        } else {
+            self.select_previous();
+        }
+        if let Some(id) = self.selected_id() {
+            self.attached = Some(id);
+        }
+    }
+
+    /// Session the list selection or the current view points at
+    fn current(&self) -> Option<String> {
+        match self.view {
+            View::List => self.selected_id(),
+            View::Diff => self.diff.as_ref().map(|d| d.session().to_string()),
+            _ => self.target(),
+        }
+    }
+
+    /// Open the new-session form, preset to the current session's agent
+    fn open_new_form(&mut self) {
+        let agent = self
+            .current()
+            .and_then(|id| self.manager.get_session(&id))
+            .map(|s| s.agent.clone());
+        let mut agents: Vec<String> = self.config.plugins.keys().cloned().collect();
+        agents.sort();
+        let current_branch =
+            git::repo_root(self.manager.project_dir()).and_then(|repo| git::current_branch(&repo));
+
+        self.dialog = Some(Dialog::New {
+            form: NewSessionForm::new(agents, agent.as_deref(), current_branch),
+            error: None,
+        });
+    }
+
+    fn open_rename(&mut self) {
+        if let Some(session) = self.current().and_then(|id| self.manager.get_session(&id)) {
+            self.dialog = Some(Dialog::Rename {
+                id: session.id.clone(),
+                input: Input::new(session.name.as_deref().unwrap_or_default()),
+                error: None,
+            });
+        }
+    }
+
+    fn confirm_kill(&mut self) {
+        if let Some(session) = self.current().and_then(|id| self.manager.get_session(&id)) {
+            self.dialog = Some(Dialog::Kill {
+                id: session.id.clone(),
+                name: session.display_name().to_string(),
+                error: None,
+            });
+        }
+    }
+
+    /// Returns true when the TUI should exit
+    fn handle_dialog(&mut self, key: KeyEvent) -> Result<bool> {
+        let Some(dialog) = &mut self.dialog else {
+            return Ok(false);
+        };
+
+        let result = match dialog.handle_key(key) {
+            Outcome::Open => return Ok(false),
+            Outcome::Quit => return Ok(true),
+            Outcome::Cancel => Ok(()),
+            Outcome::Create(options) => self.create(options),
+            Outcome::Rename { id, name } => self.manager.rename_session(&id, &name).map(|_| self.refresh_list()),
+            Outcome::Kill { id, cleanup } => self.kill(&id, cleanup),
+        };
+        match result {
+            Ok(()) => self.dialog = None,
+            Err(e) => {
+                if let Some(dialog) = &mut self.dialog {
+                    dialog.set_error(format!("{:#}", e));
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Exit right away when no agents run here, otherwise ask first since
+    /// quitting stops them
+    fn quit(&mut self) -> bool {
+        if self.agents.is_empty() {
+            return true;
+        }
+        self.dialog = Some(Dialog::Quit {
+            running: self.agents.len(),
+        });
+        false
+    }
+
+    /// Create a session off the UI thread. It shows up as Preparing once
+    /// its workspace exists, and its agent starts when setup finishes.
+    fn create(&mut self, mut options: NewSession) -> Result<()> {
+        let id = Uuid::new_v4().to_string();
+        let name = options.name.clone().unwrap_or_else(|| id[..8].to_string());
+        options.id = Some(id.clone());
+        let mut manager = SessionManager::new(
+            self.manager.state_dir().to_path_buf(),
+            self.manager.project_dir().to_path_buf(),
+        )?;
+        let (config, events) = (self.config.clone(), self.events.sender());
+        self.creating.insert(id.clone());
+        self.message = Some(format!("Preparing session '{}'...", name));
+
+        thread::spawn(move || {
+            // Setup output also goes to the session log
+            let progress = events.clone();
+            let result = manager
+                .create_session(&config, options, &mut |line| {
+                    let notice = format!("Setting up '{}': {}", name, line.trim_end());
+                    let _ = progress.send(AppEvent::Notice(notice));
+                })
+                .map(|_| ());
+            let _ = events.send(AppEvent::Created(id, result));
+        });
+        Ok(())
+    }
+
+    /// Background creation finished; the session is placed by the reload
+    /// if the state directory watcher hasn't picked it up yet
+    fn created(&mut self, id: String, result: Result<()>) -> Result<()> {
+        self.reload_sessions()?;
+        self.creating.remove(&id);
+        self.message = match result {
+            Ok(()) => None,
+            Err(e) => Some(format!("Cannot create session: {:#}", e)),
+        };
+        Ok(())
+    }
+
+    /// Show a session the user created where they are
+    fn place(&mut self, id: String) {
+        if self.view == View::Diff {
+            self.close_diff();
+        }
+        match self.view {
+            View::List | View::Attached | View::Diff => self.attach(id),
+            View::Panes => {
+                let focused = self.focused();
+                if let (Some(layout), Some(target)) = (&mut self.layout, focused) {
+                    layout.split(&target, id.clone(), Split::Horizontal);
+                }
+                self.zoomed = false;
+                self.focus_session(&id);
+            }
+        }
+    }
+
+    /// Stop the agent and remove the session, and its worktree with `cleanup`
+    fn kill(&mut self, id: &str, cleanup: bool) -> Result<()> {
+        let id = id.to_string();
+        self.agents.remove(&id);
+        self.ended.remove(&id);
+        self.manager.kill_session(&id, cleanup)?;
+        self.overlaps.retain(|o| !o.involves(&id));
+        self.overlaps_due = true;
+        self.refresh_list();
+
+        if self.diff.as_ref().is_some_and(|d| d.session() == id) {
+            self.close_diff();
+        }
+        if self.copy.as_ref().is_some_and(|c| c.session() == id) {
+            self.copy = None;
+        }
+        if self.layout.as_ref().is_some_and(|l| l.contains(&id)) {
+            self.layout = self.layout.take().and_then(|l| l.remove(&id));
+            self.zoomed = false;
+            self.typing = false;
+            let count = self.layout.as_ref().map(|l| l.panes().len()).unwrap_or(0);
+            self.focus = self.focus.min(count.saturating_sub(1));
+        }
+        if (self.layout.is_none() && self.view != View::Diff) || self.attached.as_deref() == Some(id.as_str()) {
+            self.attached = None;
+            self.view = View::List;
+        }
+        Ok(())
+    }
+
+    /// Show the current session's changes, or refresh them when already shown
+    fn open_diff(&mut self) {
+        let Some(session) = self.current().and_then(|id| self.manager.get_session(&id)) else {
+            return;
+        };
+        let patch = match self.manager.isolation(session).diff(session, false) {
+            Ok(diff) => diff.patch,
+            Err(e) => {
+                self.message = Some(format!("Cannot diff '{}': {:#}", session.display_name(), e));
+                return;
+            }
+        };
+
+        let mut view = DiffView::new(&session.id, session.display_name(), &patch, &self.theme);
+        if view.is_empty() {
+            self.message = Some(format!("No changes in session '{}'", session.display_name()));
+            return;
+        }
+        match self.diff.take() {
+            Some(old) => {
+                if let Some(path) = old.selected_path() {
+                    view.restore(path, old.scroll_position());
+                }
+            }
+            None => {
+                self.typing = false;
+                self.diff_from = self.view;
+                self.view = View::Diff;
+            }
+        }
+        self.diff = Some(view);
+    }
+
+    fn close_diff(&mut self) {
+        if self.diff.take().is_some() && self.view == View::Diff {
+            self.view = match self.diff_from {
+                View::Panes if self.layout.is_none() => View::List,
+                view => view,
+            };
+        }
+    }
+
+    /// Split the focused pane and show the next session not on screen yet
+    fn split(&mut self, direction: Split) {
+        let (Some(layout), Some(target)) = (&self.layout, self.focused()) else {
+            return;
+        };
+
+        let sessions = self.manager.list_sessions();
+        let start = sessions.iter().position(|s| s.id == target).unwrap_or(0);
+        let next = (1..sessions.len())
+            .map(|offset| sessions[(start + offset) % sessions.len()])
+            .find(|s| !layout.contains(&s.id))
+            .map(|s| s.id.clone());
+
+        match next {
+            Some(id) => {
+                if let Some(layout) = &mut self.layout {
+                    layout.split(&target, id.clone(), direction);
+                }
+                self.zoomed = false;
+                self.focus_session(&id);
+            }
+            None => self.message = Some("Every session is already on screen".to_string()),
+        }
+    }
+
+    /// Remove the focused pane; its agent keeps running
+    fn close_pane(&mut self) {
+        let Some(target) = self.focused() else {
+            return;
+        };
+        self.layout = self.layout.take().and_then(|l| l.remove(&target));
+        self.zoomed = false;
+        match &self.layout {
+            Some(layout) => self.focus = self.focus.min(layout.panes().len() - 1),
+            None => self.view = View::List,
+        }
+    }
+
+    fn cycle_focus(&mut self, forward: bool) {
+        let count = self.layout.as_ref().map(|l| l.panes().len()).unwrap_or(0);
+        if count > 0 {
+            self.focus = if forward {
+                (self.focus + 1) % count
+            } else {
+                (self.focus + count - 1) % count
+            };
+        }
+    }
+
+    /// Start agents for panes that became visible, fit each to its pane,
+    /// deliver queued messages and notice agents that exited. A failing
+    /// agent is stopped on its own and reported in the footer.
+    fn sync_agents(&mut self, area: Rect) {
+        let visible: Vec<(String, Rect)> = match (self.view, &self.attached) {
+            (View::Panes, _) => self
+                .pane_areas(area)
+                .into_iter()
+                .map(|(id, rect)| (id.to_string(), Block::default().borders(Borders::ALL).inner(rect)))
+                .collect(),
+            (View::Attached, Some(id)) => vec![(id.clone(), attached_area(area))],
+            _ => vec![],
+        };
+
+        for (id, inner) in visible {
+            if let Some(agent) = self.agents.get_mut(&id) {
+                if let Err(e) = agent.resize(inner.height, inner.width) {
+                    self.fail_agent(&id, e);
+                }
+                continue;
+            }
+            if self.ended.contains_key(&id) {
+                continue;
+            }
+            let Some(session) = self.manager.get_session(&id) else {
+                continue;
+            };
+            match session.status {
+                SessionStatus::Preparing => continue,
+                SessionStatus::Failed => {
+                    self.ended.insert(id, "Setup failed, agent not started".to_string());
+                    continue;
+                }
+                _ => {}
+            }
+
+            let started = self
+                .config
+                .get_plugin(&session.agent)
+                .ok_or_else(|| anyhow::anyhow!("Unknown agent '{}'", session.agent))
+                .and_then(|plugin| {
+                    let host = self.manager.host(&session.id)?;
+                    let notify = self.events.output_notifier();
+                    Agent::spawn(session, plugin, host, inner.height, inner.width, notify)
+                });
+            let restarted = session.status == SessionStatus::Stopped;
+            match started {
+                Ok(agent) => {
+                    self.agents.insert(id.clone(), agent);
+                    if restarted {
+                        self.set_status(&id, SessionStatus::Running);
+                    }
+                }
+                Err(e) => {
+                    self.ended.insert(id, format!("{:#}", e));
+                }
+            }
+        }
+
+        let mut exited = vec![];
+        let mut failed = vec![];
+        for (id, agent) in &mut self.agents {
+            if let Some(code) = agent.exit_status() {
+                exited.push((id.clone(), code));
+                continue;
+            }
+            let messages = match self.manager.take_messages(id) {
+                Ok(messages) => messages,
+                Err(e) => {
+                    self.message = Some(format!("{:#}", e));
+                    continue;
+                }
+            };
+            if let Some(e) = messages.iter().find_map(|m| agent.send_line(m).err()) {
+                failed.push((id.clone(), e));
+            }
+        }
+        for (id, code) in exited {
+            self.end_agent(&id, format!("Agent exited with status {}", code));
+        }
+        for (id, e) in failed {
+            self.fail_agent(&id, e);
+        }
+    }
+
+    /// Checkpoint sessions whose agent has gone quiet, or has kept working
+    /// for a whole interval, off the UI thread
+    fn auto_checkpoint(&mut self) {
+        let now = SystemTime::now();
+        self.schedules.retain(|id, _| self.agents.contains_key(id));
+        for (id, agent) in &self.agents {
+            let Some(session) = self.manager.get_session(id) else {
+                continue;
+            };
+            let Ok(worktree) = self.manager.git_worktree(session) else {
+                continue;
+            };
+            let schedule = match self.schedules.get_mut(id) {
+                Some(schedule) => schedule,
+                None => match Schedule::new(&self.config.checkpoint, now) {
+                    Some(schedule) => self.schedules.entry(id.clone()).or_insert(schedule),
+                    None => return,
+                },
+            };
+            let Some(trigger) = schedule.due(agent.last_output(), now) else {
+                continue;
+            };
+            // A slow checkpoint still running covers this one
+            if !self.checkpointing.lock().unwrap().insert(id.clone()) {
+                continue;
             }
+
+            let (id, name, worktree) = (id.clone(), session.display_name().to_string(), worktree.to_path_buf());
+            let (busy, events) = (self.checkpointing.clone(), self.events.sender());
+            thread::spawn(move || {
+                if let Err(e) = checkpoint::create(&worktree, &id, trigger, None) {
+                    let notice = format!("{} checkpoint of '{}' failed: {:#}", trigger, name, e);
+                    let _ = events.send(AppEvent::Notice(notice));
+                }
+                busy.lock().unwrap().remove(&id);
+            });
+        }
+    }
+
+    /// Look for overlapping edits off the UI thread, at most once per
+    /// interval and only after something changed
+    fn check_overlaps(&mut self) {
+        let recent = self.overlaps_checked.is_some_and(|t| t.elapsed() < OVERLAP_INTERVAL);
+        if !self.overlaps_due || self.checking_overlaps || recent {
+            return;
+        }
+        self.overlaps_due = false;
+        self.checking_overlaps = true;
+        self.overlaps_checked = Some(Instant::now());
+
+        let sessions: Vec<Session> = self.manager.list_sessions().into_iter().cloned().collect();
+        let events = self.events.sender();
+        thread::spawn(move || {
+            let overlaps = overlap::detect(&sessions.iter().collect::<Vec<_>>());
+            let _ = events.send(AppEvent::Overlaps(overlaps));
+        });
+    }
+
+    /// Areas of the visible panes; only the focused one when zoomed
+    fn pane_areas(&self, area: Rect) -> Vec<(&str, Rect)> {
+        let body = chunks(area)[1];
+        let Some(layout) = &self.layout else {
+            return vec![];
+        };
+        if self.zoomed {
+            let panes = layout.panes();
+            return panes.get(self.focus).map(|id| vec![(*id, body)]).unwrap_or_default();
+        }
+        layout.areas(body)
+    }
+
+    /// Whether keys go to an agent, leaving only key sequences to the TUI
+    fn is_typing(&self) -> bool {
+        self.typing || self.view == View::Attached
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Result<bool> {
+        self.message = None;
+        if self.dialog.is_some() {
+            return self.handle_dialog(key);
         }
+        if self.filtering {
+            self.edit_filter(key);
+            return Ok(false);
+        }
+        if self.copy.is_some() {
+            self.handle_copy(key)?;
+            return Ok(false);
+        }
+        self.pending.push(key);
+        let keys: Vec<Chord> = self.pending.iter().map(|k| Chord::from(*k)).collect();
+
+        match self.keymap.lookup(&keys, self.is_typing()) {
+            Lookup::Pending => Ok(false),
+            Lookup::Action(action) => {
+                let pending = std::mem::take(&mut self.pending);
+                self.run(action, pending[0])
+            }
+            Lookup::Unbound => {
+                self.pending.clear();
+                if keys.len() > 1 {
+                    let keys: Vec<String> = keys.iter().map(Chord::to_string).collect();
+                    self.message = Some(format!("{}: not bound", keys.join(" ")));
+                } else if self.is_typing() {
+                    self.forward(key);
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    /// Run a bound action; `first` is the first key of its sequence.
+    /// Returns true when the TUI should exit.
+    fn run(&mut self, action: Action, first: KeyEvent) -> Result<bool> {
+        match (action, self.view) {
+            (Action::Quit, _) | (Action::Back, View::List) => return Ok(self.quit()),
+            (Action::Back, View::Panes) if !self.typing => self.view = View::List,
+            (Action::Back, View::Diff) => self.close_diff(),
+            (Action::Back | Action::Detach, _) => self.detach(),
+            (Action::Next, View::List) => self.select_next(),
+            (Action::Prev, View::List) => self.select_previous(),
+            (
+                Action::Next
+                | Action::Prev
+                | Action::PageDown
+                | Action::PageUp
+                | Action::NextFile
+                | Action::PrevFile
+                | Action::NextHunk
+                | Action::PrevHunk,
+                View::Diff,
+            ) => self.scroll_diff(action),
+            (Action::Next, _) => self.switch(true),
+            (Action::Prev, _) => self.switch(false),
+            (Action::Attach, View::List) => {
+                if let Some(id) = self.selected_id() {
+                    self.attach(id);
+                }
+            }
+            (Action::Attach, View::Panes) => match self.focused() {
+                Some(id) if self.agents.contains_key(&id) => self.typing = true,
+                Some(_) => self.message = Some("No agent running in this pane".to_string()),
+                None => {}
+            },
+            (Action::Pane, View::List | View::Attached) => self.open_selected(),
+            (Action::Panes, View::List | View::Attached) if self.layout.is_some() => {
+                self.view = View::Panes
+            }
+            (Action::Panes, View::Panes) => {
+                self.typing = false;
+                self.view = View::List;
+            }
+            (Action::SplitHorizontal, View::Panes) => self.split(Split::Horizontal),
+            (Action::SplitVertical, View::Panes) => self.split(Split::Vertical),
+            (Action::Zoom, View::Panes) => self.zoomed = !self.zoomed,
+            (Action::Close, View::Panes) => self.close_pane(),
+            (Action::Layout, View::Panes) => self.cycle_preset(),
+            (Action::New, _) => self.open_new_form(),
+            (Action::Rename, _) => self.open_rename(),
+            (Action::Kill, _) => self.confirm_kill(),
+            (Action::Filter, View::List) => self.filtering = true,
+            (Action::NextWaiting, _) => self.next_waiting(),
+            (Action::Sort, View::List) => {
+                self.sort = self.sort.next();
+                self.refresh_list();
+            }
+            (Action::Diff, View::List | View::Panes | View::Diff) if !self.typing => self.open_diff(),
+            (Action::CopyMode, View::Panes | View::Attached) => self.enter_copy_mode(),
+            (Action::SendPrefix, _) if self.is_typing() => self.forward(first),
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn scroll_diff(&mut self, action: Action) {
+        let Some(diff) = &mut self.diff else {
+            return;
+        };
+        match action {
+            Action::Next => diff.scroll(1),
+            Action::Prev => diff.scroll(-1),
+            Action::PageDown => diff.page(true),
+            Action::PageUp => diff.page(false),
+            Action::NextFile => diff.next_file(),
+            Action::PrevFile => diff.prev_file(),
+            Action::NextHunk => diff.next_hunk(),
+            Action::PrevHunk => diff.prev_hunk(),
+            _ => {}
+        }
+    }
+
+    /// Snapshot the scrollback of the session keys would go to
+    fn enter_copy_mode(&mut self) {
+        let Some(id) = self.target() else {
+            return;
+        };
+        match self.agents.get(&id) {
+            Some(agent) => {
+                let mut parser = agent.parser();
+                self.copy = Some(CopyMode::new(&id, &mut parser, self.config.tui.copy_file()));
+            }
+            None => self.message = Some("No agent running in this pane".to_string()),
+        }
+    }
+
+    fn handle_copy(&mut self, key: KeyEvent) -> Result<()> {
+        let Some(copy) = &mut self.copy else {
+            return Ok(());
+        };
+
+        match copy.handle_key(key) {
+            copy::Outcome::Open => {}
+            copy::Outcome::Exit => self.copy = None,
+            copy::Outcome::Message(message) => self.message = Some(message),
+            copy::Outcome::Copy(text) => {
+                let mut stdout = io::stdout();
+                stdout.write_all(copy::osc52(&text).as_bytes())?;
+                stdout.flush()?;
+                self.message = Some(format!("Copied {} line(s) to the clipboard", text.lines().count().max(1)));
+                self.copy = None;
+            }
+            copy::Outcome::Save { path, text } => match save_copy(&path, &text) {
+                Ok(path) => {
+                    self.message = Some(format!("Saved {} line(s) to {}", text.lines().count().max(1), path.display()));
+                    self.copy = None;
+                }
+                Err(e) => self.message = Some(format!("{:#}", e)),
+            },
+        }
+        Ok(())
+    }
+
+    fn forward(&mut self, key: KeyEvent) {
+        let Some(id) = self.target() else {
+            return;
+        };
+        let Some(agent) = self.agents.get_mut(&id) else {
+            return;
+        };
+        let app_cursor = agent.parser().screen().application_cursor();
+        if let Err(e) = agent.write(&pane::encode_key(key, app_cursor)) {
+            self.fail_agent(&id, e);
+            return;
+        }
+        // Answering the agent takes it off the attention queue
+        if self.manager.get_session(&id).is_some_and(|s| s.status == SessionStatus::NeedsInput) {
+            self.set_status(&id, SessionStatus::Running);
+        }
+    }
+
+    /// Record a session's status, reporting a failure to save it in the footer
+    fn set_status(&mut self, id: &str, status: SessionStatus) {
+        if let Err(e) = self.manager.update_status(id, status) {
+            self.message = Some(format!("{:#}", e));
+        }
+    }
+
+    /// Drop a session's agent after it exited or failed; the session stays,
+    /// showing `reason` in place of the agent
+    fn end_agent(&mut self, id: &str, reason: String) {
+        self.agents.remove(id);
+        self.ended.insert(id.to_string(), reason);
+        if self.manager.get_session(id).is_some() {
+            self.set_status(id, SessionStatus::Stopped);
+        }
+        if self.view == View::Panes && self.focused().as_deref() == Some(id) {
+            self.typing = false;
+        }
+    }
+
+    /// Stop one agent whose terminal failed, keeping the others running
+    fn fail_agent(&mut self, id: &str, error: anyhow::Error) {
+        self.end_agent(id, format!("Agent stopped: {:#}", error));
+        let name = self.manager.get_session(id).map(|s| s.display_name()).unwrap_or(&id[..8]);
+        self.message = Some(format!("Agent of '{}' stopped: {:#}", name, error));
     }
 }
 
+/// Write text selected in copy mode to `path`, `~/` meaning the home directory
+fn save_copy(path: &str, text: &str) -> Result<PathBuf> {
+    let path = match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .context("Could not determine home directory")?
+            .join(rest),
+        None => PathBuf::from(path),
+    };
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    fs::write(&path, format!("{}\n", text)).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
 pub async fn run() -> Result<()> {
     // Setup terminal
     enable_raw_mode()?;
@@ -55,10 +1133,10 @@ pub async fn run() -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // Create app
-    let mut app = App::new()?;
-
-    // Run app
-    let res = run_app(&mut terminal, &mut app).await;
+    let res = match App::new() {
+        Ok(mut app) => run_app(&mut terminal, &mut app).await,
+        Err(e) => Err(e),
+    };
 
     // Restore terminal
     disable_raw_mode()?;
@@ -77,22 +1155,91 @@ pub async fn run() -> Result<()> {
 }
 
 async fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> Result<()> {
+    app.reload_layout();
+    let mut changed = true;
+
     loop {
-        terminal.draw(|f| ui(f, app))?;
+        if changed {
+            let size = terminal.size()?;
+            app.sync_agents(Rect::new(0, 0, size.width, size.height));
+            terminal.draw(|f| ui(f, app))?;
+        }
 
-        if let Event::Key(key) = event::read()? {
-            match key.code {
-                KeyCode::Char('q') => return Ok(()),
-                KeyCode::Down | KeyCode::Char('j') => app.select_next(),
-                KeyCode::Up | KeyCode::Char('k') => app.select_previous(),
-                _ => {}
+        // Handle everything that queued up before drawing again
+        let mut event = Some(app.events.next(TICK));
+        changed = false;
+        while let Some(current) = event {
+            match current {
+                AppEvent::Input(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                    if app.handle_key(key)? {
+                        return Ok(());
+                    }
+                    app.store_layout()?;
+                    changed = true;
+                }
+                AppEvent::Input(Event::Resize(..)) => changed = true,
+                AppEvent::Input(_) => {}
+                AppEvent::Sessions => {
+                    app.reload_sessions()?;
+                    changed = true;
+                }
+                AppEvent::Layout => {
+                    app.reload_layout();
+                    changed = true;
+                }
+                AppEvent::Notice(notice) => {
+                    app.message = Some(notice);
+                    changed = true;
+                }
+                AppEvent::Created(id, result) => {
+                    app.created(id, result)?;
+                    changed = true;
+                }
+                AppEvent::Output => {
+                    // Agent output counts as activity, and may come with
+                    // edits overlapping other sessions
+                    if app.sort == SortKey::Activity {
+                        app.refresh_list();
+                    }
+                    app.overlaps_due = true;
+                    changed = true;
+                }
+                AppEvent::Activity => {
+                    if app.sort == SortKey::Activity {
+                        app.refresh_list();
+                    }
+                    // Redrawing delivers queued messages
+                    changed = true;
+                }
+                AppEvent::Overlaps(overlaps) => {
+                    let sessions = app.manager.list_sessions();
+                    let exists = |id: &str| sessions.iter().any(|s| s.id == id);
+                    app.overlaps = overlaps.into_iter().filter(|o| exists(&o.first) && exists(&o.second)).collect();
+                    app.checking_overlaps = false;
+                    changed = true;
+                }
+                AppEvent::Tick => {
+                    if !app.events.watching() {
+                        app.reload_sessions()?;
+                        app.reload_layout();
+                    }
+                    // Agents hosted by other acta processes edit files
+                    // without output showing up here
+                    app.overlaps_due = true;
+                    // Uptimes are only on the session list
+                    changed |= app.view == View::List || !app.events.watching();
+                }
             }
+            event = app.events.pending();
         }
+        app.auto_checkpoint();
+        app.check_overlaps();
     }
 }
 
-fn ui(f: &mut Frame, app: &App) {
-    let chunks = Layout::default()
+/// Header, body and footer areas
+fn chunks(area: Rect) -> Rc<[Rect]> {
+    Layout::default()
         .direction(Direction::Vertical)
         .margin(2)
         .constraints([
@@ -100,32 +1247,274 @@ fn ui(f: &mut Frame, app: &App) {
             Constraint::Min(0),
             Constraint::Length(3),
         ])
-        .split(f.area());
+        .split(area)
+}
+
+/// Terminal area of the attached view, above its status line
+fn attached_area(area: Rect) -> Rect {
+    Rect {
+        height: area.height.saturating_sub(1),
+        ..area
+    }
+}
+
+fn ui(f: &mut Frame, app: &App) {
+    match app.view {
+        View::Attached => attached(f, app),
+        _ => overview(f, app),
+    }
+    if let Some(dialog) = &app.dialog {
+        dialog.render(f, &app.theme);
+    }
+}
+
+/// Session list or panes, between the header and the help footer
+fn overview(f: &mut Frame, app: &App) {
+    let chunks = chunks(f.area());
 
     // Header
-    let header = Paragraph::new("Acta - Agentic Terminal Multiplexer")
-        .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
-        .block(Block::default().borders(Borders::ALL).title("Header"));
+    let mut title = "Acta - Agentic Terminal Multiplexer".to_string();
+    if !app.overlaps.is_empty() {
+        title.push_str(&format!("  ⚠ {} overlapping edit(s)", app.overlaps.len()));
+    }
+    let mut spans = vec![Span::raw(title)];
+    if let Some(badge) = waiting_badge(app) {
+        spans.push(Span::raw("  "));
+        spans.push(badge);
+    }
+    let header = Paragraph::new(Line::from(spans))
+        .style(app.theme.header)
+        .block(border(app).title("Header"));
     f.render_widget(header, chunks[0]);
 
-    // Sessions list
-    let sessions = app.manager.list_sessions();
+    match (app.view, &app.diff) {
+        (View::Diff, Some(diff)) => diff.render(f, chunks[1]),
+        (View::List | View::Diff, _) => session_list(f, app, chunks[1]),
+        (View::Panes | View::Attached, _) => panes(f, app),
+    }
+
+    // Footer
+    if app.filtering {
+        let text = format!("/{}", app.filter.text());
+        let cursor = (chunks[2].x + 1 + text.chars().count() as u16, chunks[2].y + 1);
+        let footer = Paragraph::new(text).block(border(app).title("Filter (Enter: keep | Esc: clear | ↑/↓: select)"));
+        f.render_widget(footer, chunks[2]);
+        f.set_cursor_position(cursor);
+        return;
+    }
+    if let Some((title, text)) = app.copy.as_ref().and_then(CopyMode::prompt) {
+        let cursor = (chunks[2].x + 1 + text.chars().count() as u16, chunks[2].y + 1);
+        let footer = Paragraph::new(text).block(border(app).title(title));
+        f.render_widget(footer, chunks[2]);
+        f.set_cursor_position(cursor);
+        return;
+    }
+    let title = app.copy.as_ref().map(CopyMode::status).unwrap_or_else(|| "Help".to_string());
+    let footer = Paragraph::new(app.message.clone().unwrap_or_else(|| help(app)))
+        .style(app.theme.footer)
+        .block(border(app).title(title));
+    f.render_widget(footer, chunks[2]);
+}
+
+/// Bordered block in the theme's border style
+fn border(app: &App) -> Block<'static> {
+    Block::default().borders(Borders::ALL).border_style(app.theme.border)
+}
+
+/// How many sessions wait for input, when any do
+fn waiting_badge(app: &App) -> Option<Span<'static>> {
+    let count = app.waiting().len();
+    (count > 0).then(|| {
+        Span::styled(format!(" ⏳ {} waiting for input ", count), app.theme.badge)
+    })
+}
+
+/// Bindings for what the current view offers, or how to finish a sequence
+fn help(app: &App) -> String {
+    let typed: Vec<Chord> = app.pending.iter().map(|k| Chord::from(*k)).collect();
+    if !typed.is_empty() {
+        let next: Vec<String> = app
+            .keymap
+            .continuations(&typed)
+            .map(|b| format!("{} {}", b.keys[typed.len()..].iter().map(Chord::to_string).collect::<Vec<_>>().join(" "), b.action.name()))
+            .collect();
+        let typed: Vec<String> = typed.iter().map(Chord::to_string).collect();
+        return format!("{} pressed: {}", typed.join(" "), next.join(" | "));
+    }
+    if app.copy.is_some() {
+        return copy::HELP.to_string();
+    }
+
+    let actions: &[Action] = match (app.view, app.is_typing()) {
+        (_, true) => &[
+            Action::Detach,
+            Action::Next,
+            Action::Prev,
+            Action::NextWaiting,
+            Action::CopyMode,
+            Action::New,
+            Action::Zoom,
+            Action::Layout,
+        ],
+        (View::List, false) => &[
+            Action::Back,
+            Action::Next,
+            Action::Prev,
+            Action::Attach,
+            Action::Pane,
+            Action::Panes,
+            Action::New,
+            Action::Rename,
+            Action::Kill,
+            Action::Diff,
+            Action::Filter,
+            Action::Sort,
+            Action::NextWaiting,
+        ],
+        (View::Diff, false) => &[
+            Action::Back,
+            Action::Next,
+            Action::Prev,
+            Action::NextFile,
+            Action::PrevFile,
+            Action::NextHunk,
+            Action::PrevHunk,
+            Action::PageDown,
+            Action::PageUp,
+            Action::Diff,
+        ],
+        (_, false) => &[
+            Action::Back,
+            Action::Next,
+            Action::Prev,
+            Action::Attach,
+            Action::SplitHorizontal,
+            Action::SplitVertical,
+            Action::Zoom,
+            Action::Close,
+            Action::Layout,
+            Action::CopyMode,
+            Action::Panes,
+        ],
+    };
+
+    actions
+        .iter()
+        .filter_map(|action| {
+            let keys = app.keymap.keys_for(*action, app.is_typing());
+            let keys: Vec<&str> = keys.iter().take(2).map(String::as_str).collect();
+            (!keys.is_empty()).then(|| format!("{}: {}", keys.join("/"), action.name()))
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+fn attached(f: &mut Frame, app: &App) {
+    let area = attached_area(f.area());
+    let id = app.attached.as_deref().unwrap_or_default();
+
+    match (app.agents.get(id), &app.copy) {
+        (_, Some(copy)) if copy.session() == id => {
+            if let Some(position) = copy.render(area, f.buffer_mut(), &app.theme) {
+                f.set_cursor_position(position);
+            }
+        }
+        (Some(agent), _) => {
+            let parser = agent.parser();
+            pane::render_screen(parser.screen(), area, f.buffer_mut());
+            if app.pending.is_empty() {
+                if let Some(position) = pane::cursor(parser.screen(), area) {
+                    f.set_cursor_position(position);
+                }
+            }
+        }
+        (None, _) => {
+            let text = app.ended.get(id).map(String::as_str).unwrap_or("Waiting for setup to finish...");
+            let notice = Paragraph::new(text).style(app.theme.muted).wrap(Wrap { trim: true });
+            f.render_widget(notice, area);
+        }
+    }
+
+    let status_area = Rect::new(area.x, area.y + area.height, area.width, 1);
+    if let Some((_, text)) = app.copy.as_ref().and_then(CopyMode::prompt) {
+        let cursor = (status_area.x + text.chars().count() as u16, status_area.y);
+        let prompt = Paragraph::new(text).style(app.theme.prompt);
+        f.render_widget(prompt, status_area);
+        f.set_cursor_position(cursor);
+        return;
+    }
+    let session = match app.manager.get_session(id) {
+        Some(s) => format!(" {} ({}) [{:?}] ", s.display_name(), s.agent, s.status),
+        None => " [gone] ".to_string(),
+    };
+    let help = app
+        .message
+        .clone()
+        .unwrap_or_else(|| help(app));
+    let style = if !app.pending.is_empty() {
+        app.theme.prompt
+    } else {
+        app.theme.status_bar
+    };
+    let mut spans = vec![];
+    if let Some(badge) = waiting_badge(app) {
+        spans.push(badge);
+    }
+    if let Some(copy) = &app.copy {
+        spans.push(Span::raw(format!("{}· {} ", session, copy.status())));
+        spans.push(Span::raw(format!("| {}", help)));
+    } else {
+        spans.push(Span::raw(format!("{}| {}", session, help)));
+    }
+    let status = Paragraph::new(Line::from(spans)).style(style);
+    f.render_widget(status, status_area);
+}
+
+/// Time since `since`, as `42s`, `5m`, `3h 12m` or `2d 4h`
+fn uptime(since: SystemTime) -> String {
+    let secs = since.elapsed().map(|d| d.as_secs()).unwrap_or(0);
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d {}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
+
+fn session_list(f: &mut Frame, app: &App, area: Rect) {
+    let sessions = app.sessions();
     let items: Vec<ListItem> = sessions
         .iter()
         .enumerate()
         .map(|(i, session)| {
-            let content = format!(
-                "{} {} [{}] {}",
-                &session.id[..8],
-                session.agent,
-                format!("{:?}", session.status),
-                session.name.as_deref().unwrap_or("-")
-            );
+            let warning = if app.overlaps.iter().any(|o| o.involves(&session.id)) {
+                " ⚠"
+            } else {
+                ""
+            };
+            let live = if app.agents.contains_key(&session.id) {
+                " ●"
+            } else {
+                ""
+            };
+            let tags: String = session.tags.iter().map(|t| format!(" #{}", t)).collect();
+            let content = Line::from(vec![
+                Span::raw(format!("{} {} ", &session.id[..8], session.agent)),
+                Span::styled(format!("[{:?}]", session.status), app.theme.status(&session.status)),
+                Span::raw(format!(
+                    " {}{} up {}{}{}",
+                    session.name.as_deref().unwrap_or("-"),
+                    tags,
+                    uptime(session.created_at),
+                    live,
+                    warning
+                )),
+            ]);
 
             let style = if i == app.selected {
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD)
+                app.theme.selected
+            } else if session.status == SessionStatus::NeedsInput {
+                app.theme.status(&session.status)
             } else {
                 Style::default()
             };
@@ -134,18 +1523,71 @@ fn ui(f: &mut Frame, app: &App) {
         })
         .collect();
 
-    let sessions_list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title(format!(
-            "Sessions ({}/{})",
-            app.selected + 1,
-            sessions.len().max(1)
-        )));
+    let position = if sessions.is_empty() { 0 } else { app.selected + 1 };
+    let mut title = format!("Sessions ({}/{})", position, sessions.len());
+    if !app.filter.value().is_empty() {
+        title.push_str(&format!(
+            " · /{} matches {} of {}",
+            app.filter.value(),
+            sessions.len(),
+            app.manager.list_sessions().len()
+        ));
+    }
+    if app.sort != SortKey::Created {
+        title.push_str(&format!(" · by {}", app.sort.name()));
+    }
+    let sessions_list = List::new(items).block(border(app).title(title));
 
-    f.render_widget(sessions_list, chunks[1]);
+    f.render_widget(sessions_list, area);
+}
 
-    // Footer
-    let footer = Paragraph::new("q: quit | ↑/k: up | ↓/j: down | Enter: attach (not implemented)")
-        .style(Style::default().fg(Color::Gray))
-        .block(Block::default().borders(Borders::ALL).title("Help"));
-    f.render_widget(footer, chunks[2]);
+fn panes(f: &mut Frame, app: &App) {
+    let focused = app.focused();
+
+    for (id, area) in app.pane_areas(f.area()) {
+        let is_focused = focused.as_deref() == Some(id);
+        let mut title = match app.manager.get_session(id) {
+            Some(s) => format!(" {} ({}) [{:?}] ", s.display_name(), s.agent, s.status),
+            None => format!(" {} [gone] ", &id[..8]),
+        };
+        let copy = app.copy.as_ref().filter(|c| is_focused && c.session() == id);
+        if copy.is_some() {
+            title.push_str("[copy mode] ");
+        }
+        let waiting = app
+            .manager
+            .get_session(id)
+            .is_some_and(|s| s.status == SessionStatus::NeedsInput);
+        let border = match (is_focused, app.typing) {
+            (true, true) => app.theme.border_typing,
+            (true, false) => app.theme.border_focused,
+            _ if waiting => app.theme.status(&SessionStatus::NeedsInput),
+            _ => app.theme.border_inactive,
+        };
+        let block = Block::default().borders(Borders::ALL).title(title).border_style(border);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        match (app.agents.get(id), copy) {
+            (_, Some(copy)) => {
+                if let Some(position) = copy.render(inner, f.buffer_mut(), &app.theme) {
+                    f.set_cursor_position(position);
+                }
+            }
+            (Some(agent), None) => {
+                let parser = agent.parser();
+                pane::render_screen(parser.screen(), inner, f.buffer_mut());
+                if is_focused && app.typing {
+                    if let Some(position) = pane::cursor(parser.screen(), inner) {
+                        f.set_cursor_position(position);
+                    }
+                }
+            }
+            (None, None) => {
+                let text = app.ended.get(id).map(String::as_str).unwrap_or("Waiting for setup to finish...");
+                let notice = Paragraph::new(text).style(app.theme.muted).wrap(Wrap { trim: true });
+                f.render_widget(notice, inner);
+            }
+        }
+    }
 }
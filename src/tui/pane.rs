@@ -0,0 +1,117 @@
+// Drawing agent terminals into panes and forwarding keys to them
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+
+/// Copy the emulated screen into `area` of the frame buffer
+pub fn render_screen(screen: &vt100::Screen, area: Rect, buf: &mut Buffer) {
+    for row in 0..area.height {
+        for col in 0..area.width {
+            let Some(cell) = screen.cell(row, col) else {
+                continue;
+            };
+            if cell.is_wide_continuation() {
+                continue;
+            }
+            let Some(target) = buf.cell_mut((area.x + col, area.y + row)) else {
+                continue;
+            };
+
+            let contents = cell.contents();
+            target.set_symbol(if contents.is_empty() { " " } else { contents });
+            target.set_style(cell_style(cell));
+        }
+    }
+}
+
+/// Cursor position within `area`, if the program shows one
+pub fn cursor(screen: &vt100::Screen, area: Rect) -> Option<(u16, u16)> {
+    if screen.hide_cursor() {
+        return None;
+    }
+    let (row, col) = screen.cursor_position();
+    (row < area.height && col < area.width).then_some((area.x + col, area.y + row))
+}
+
+pub fn cell_style(cell: &vt100::Cell) -> Style {
+    let mut style = Style::default()
+        .fg(color(cell.fgcolor()))
+        .bg(color(cell.bgcolor()));
+    if cell.bold() {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    if cell.italic() {
+        style = style.add_modifier(Modifier::ITALIC);
+    }
+    if cell.underline() {
+        style = style.add_modifier(Modifier::UNDERLINED);
+    }
+    if cell.inverse() {
+        style = style.add_modifier(Modifier::REVERSED);
+    }
+    style
+}
+
+fn color(color: vt100::Color) -> Color {
+    match color {
+        vt100::Color::Default => Color::Reset,
+        vt100::Color::Idx(i) => Color::Indexed(i),
+        vt100::Color::Rgb(r, g, b) => Color::Rgb(r, g, b),
+    }
+}
+
+/// Bytes a terminal would send for `key`. `app_cursor` selects the
+/// application cursor key encoding some full-screen programs switch on.
+pub fn encode_key(key: KeyEvent, app_cursor: bool) -> Vec<u8> {
+    let cursor = |c: char| {
+        if app_cursor {
+            format!("\x1bO{}", c).into_bytes()
+        } else {
+            format!("\x1b[{}", c).into_bytes()
+        }
+    };
+
+    let mut bytes = match key.code {
+        KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            match c.to_ascii_lowercase() {
+                c @ 'a'..='z' => vec![c as u8 - b'a' + 1],
+                '@' | ' ' => vec![0],
+                '[' => vec![0x1b],
+                '\\' => vec![0x1c],
+                ']' => vec![0x1d],
+                '^' => vec![0x1e],
+                '_' => vec![0x1f],
+                _ => c.to_string().into_bytes(),
+            }
+        }
+        KeyCode::Char(c) => c.to_string().into_bytes(),
+        KeyCode::Enter => vec![b'\r'],
+        KeyCode::Backspace => vec![0x7f],
+        KeyCode::Tab => vec![b'\t'],
+        KeyCode::BackTab => b"\x1b[Z".to_vec(),
+        KeyCode::Esc => vec![0x1b],
+        KeyCode::Up => cursor('A'),
+        KeyCode::Down => cursor('B'),
+        KeyCode::Right => cursor('C'),
+        KeyCode::Left => cursor('D'),
+        KeyCode::Home => cursor('H'),
+        KeyCode::End => cursor('F'),
+        KeyCode::PageUp => b"\x1b[5~".to_vec(),
+        KeyCode::PageDown => b"\x1b[6~".to_vec(),
+        KeyCode::Insert => b"\x1b[2~".to_vec(),
+        KeyCode::Delete => b"\x1b[3~".to_vec(),
+        KeyCode::F(n @ 1..=4) => format!("\x1bO{}", (b'P' + n - 1) as char).into_bytes(),
+        KeyCode::F(n @ 5..=12) => {
+            let code = [15, 17, 18, 19, 20, 21, 23, 24][(n - 5) as usize];
+            format!("\x1b[{}~", code).into_bytes()
+        }
+        _ => vec![],
+    };
+
+    if key.modifiers.contains(KeyModifiers::ALT) && !bytes.is_empty() {
+        bytes.insert(0, 0x1b);
+    }
+    bytes
+}